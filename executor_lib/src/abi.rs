@@ -0,0 +1,47 @@
+//! Versions of the ABI between the executors and the wasm blobs.
+//!
+//! The Host Functions of every ABI version are imported from their own namespace,
+//! `env_v1`, `env_v2`, ..., so a new version can change a Host Function without breaking
+//! the wasm blobs compiled against an older one. The wasm codes written before the
+//! versions import from `env`, which is the same as `env_v1`.
+
+use wasmtime::Module;
+
+/// Namespace of the Host Functions written before the ABI versions
+pub const LEGACY_NAMESPACE: &str = "env";
+
+/// ABI version of the Host Functions in `LEGACY_NAMESPACE`
+pub const LEGACY_VERSION: u32 = 1;
+
+const NAMESPACE_PREFIX: &str = "env_v";
+
+/// Namespace of the Host Functions of an ABI version
+pub fn namespace(version: u32) -> String {
+	format!("{}{}", NAMESPACE_PREFIX, version)
+}
+
+/// ABI version of a namespace, missing if it does not contain Host Functions of an ABI version
+pub fn version(namespace: &str) -> Option<u32> {
+	if namespace == LEGACY_NAMESPACE {
+		return Some(LEGACY_VERSION)
+	}
+	namespace
+		.strip_prefix(NAMESPACE_PREFIX)?
+		.parse()
+		.ok()
+		.filter(|version| *version > 0)
+}
+
+/// ABI version used by a wasm blob, the highest version among the namespaces of its imports.
+/// Missing if the blob does not import any Host Function of an ABI version.
+pub fn blob_version(module: &Module) -> Option<u32> {
+	module.imports().filter_map(|import| version(import.module())).max()
+}
+
+/// Format an optional ABI version as printed by the executors
+pub fn show(version: Option<u32>) -> String {
+	match version {
+		Some(version) => format!("v{}", version),
+		None => "none".to_string(),
+	}
+}
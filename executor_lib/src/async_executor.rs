@@ -0,0 +1,111 @@
+//! Asynchronous execution of the wasm blobs.
+//!
+//! The AsyncExecutor drives `start` as a future, so the executor can run inside an async
+//! runtime and its host modules can define async Host Functions with the `func_wrapN_async`
+//! methods of the Linker, e.g. to read the SharedState from an async store.
+//!
+//! The wasm code yields cooperatively to the runtime at every epoch, incremented by a
+//! ticker thread at a fixed interval, so a long execution does not block the other tasks.
+//! The fuel is not used to yield: it stays the limit of the execution, and the charges
+//! of the Host Functions, see `weight`, could not be split between the slices of fuel.
+
+use crate::{
+	executor::{backtrace_config, Execution, Executor},
+	host::Registry,
+	wasm_codes::WasmCodes,
+	State,
+};
+use std::{
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
+	thread,
+	time::Duration,
+};
+use wasmtime::Engine;
+
+// Time between two yields of the wasm code
+const YIELD_INTERVAL: Duration = Duration::from_millis(1);
+
+// Increments the epoch of the Engine until it is dropped
+struct Ticker {
+	stop: Arc<AtomicBool>,
+}
+
+impl Ticker {
+	fn start(engine: Engine) -> Self {
+		let stop = Arc::new(AtomicBool::new(false));
+		let stopped = stop.clone();
+		thread::spawn(move || {
+			while !stopped.load(Ordering::Relaxed) {
+				thread::sleep(YIELD_INTERVAL);
+				engine.increment_epoch();
+			}
+		});
+		Ticker { stop }
+	}
+}
+
+impl Drop for Ticker {
+	fn drop(&mut self) {
+		self.stop.store(true, Ordering::Relaxed);
+	}
+}
+
+/// Executor of the wasm blobs over a SharedState `S` for async runtimes, e.g. tokio.
+///
+/// Same as the Executor, but the host modules of its Registry can define async Host
+/// Functions and the executions are futures. A Registry with async Host Functions
+/// can't be used by the Executor.
+pub struct AsyncExecutor<S: 'static> {
+	executor: Executor<S>,
+	_ticker: Ticker,
+}
+
+impl<S: State + Send> AsyncExecutor<S> {
+	pub fn new(wasm_codes: WasmCodes, registry: Registry<S>) -> Result<Self, String> {
+		let mut config = backtrace_config();
+		config.async_support(true).epoch_interruption(true);
+		let executor = Executor::with_engine(config, wasm_codes, registry)?;
+		let ticker = Ticker::start(executor.engine().clone());
+		Ok(AsyncExecutor { executor, _ticker: ticker })
+	}
+
+	pub fn wasm_codes(&self) -> &WasmCodes {
+		self.executor.wasm_codes()
+	}
+
+	/// Compile the wasm blob and resolve its imports,
+	/// a blob already loaded with the same name is replaced.
+	pub fn load(&mut self, name: &str) -> Result<(), String> {
+		self.executor.load(name)
+	}
+
+	/// Forget a loaded blob, the next execution will load it again
+	pub fn unload(&mut self, name: &str) {
+		self.executor.unload(name)
+	}
+
+	/// Forget a loaded blob whose wasm code changed, see `Executor::refresh`
+	pub fn refresh(&mut self, name: &str) {
+		self.executor.refresh(name)
+	}
+
+	/// ABI version used by a wasm blob, the blob is loaded if it is not already
+	pub fn abi_version(&mut self, name: &str) -> Result<Option<u32>, String> {
+		self.executor.abi_version(name)
+	}
+
+	/// Execute a wasm blob over the SharedState, the blob is loaded if it is not already.
+	///
+	/// The Result is the new SharedState or an Error containing a String describing the Error
+	pub async fn execute(&mut self, name: &str, shared_state: S) -> Result<S, String> {
+		self.run(name, shared_state).await.result
+	}
+
+	/// Same as `execute` but everything observed during the execution is returned
+	pub async fn run(&mut self, name: &str, shared_state: S) -> Execution<S> {
+		self.executor.run_async(name, shared_state).await
+	}
+}
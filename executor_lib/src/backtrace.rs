@@ -0,0 +1,93 @@
+//! Readable backtraces of the wasm code.
+//!
+//! wasmtime captures the wasm frames of a trap, they are symbolicated with the function
+//! names of the name section and, if the wasm code contains DWARF debug information
+//! (e.g. it is built in debug mode), with the source file and line of every frame.
+
+use rustc_demangle::demangle;
+use serde::Serialize;
+use std::fmt;
+use wasmtime::{FrameInfo, WasmBacktrace};
+
+// Crates of the panic runtime, their frames are not interesting once the panic is reported
+const PANIC_RUNTIME: [&str; 4] = ["core::", "std::", "__rustc::", "panic_abort::"];
+
+/// A frame of the wasm stack
+#[derive(Clone, Serialize)]
+pub struct Frame {
+	/// Demangled name of the function, `<wasm function N>` if the name section is missing
+	pub function: String,
+	/// Source file and line, only with DWARF
+	pub file: Option<String>,
+	pub line: Option<u32>,
+}
+
+impl Frame {
+	pub(crate) fn new(frame: &FrameInfo) -> Self {
+		let function = match frame.func_name() {
+			// The alternate format omits the hashes of the mangled names
+			Some(name) => format!("{:#}", demangle(name)),
+			None => format!("<wasm function {}>", frame.func_index()),
+		};
+		// The first symbol is the innermost inlined function, where the frame is
+		let symbol = frame.symbols().first();
+		Frame {
+			function,
+			file: symbol.and_then(|symbol| symbol.file()).map(str::to_string),
+			line: symbol.and_then(|symbol| symbol.line()),
+		}
+	}
+
+	fn in_panic_runtime(&self) -> bool {
+		PANIC_RUNTIME.iter().any(|krate| self.function.starts_with(krate))
+	}
+}
+
+/// The wasm frames of a failed execution, from the entry point to the one that failed
+#[derive(Clone, Serialize)]
+#[serde(transparent)]
+pub struct Backtrace(pub Vec<Frame>);
+
+impl Backtrace {
+	/// The backtrace captured by wasmtime with the error of an execution, if any.
+	/// If the wasm code `panicked` the innermost frames of the panic runtime are removed.
+	pub fn from_error(err: &wasmtime::Error, panicked: bool) -> Option<Self> {
+		let backtrace = err.downcast_ref::<WasmBacktrace>()?;
+		// wasmtime lists the frames starting from the innermost
+		let mut frames: Vec<_> = backtrace.frames().iter().rev().map(Frame::new).collect();
+		if panicked {
+			let panic_frames = frames.iter().rev().take_while(|frame| frame.in_panic_runtime());
+			frames.truncate(frames.len() - panic_frames.count());
+		}
+		(!frames.is_empty()).then_some(Backtrace(frames))
+	}
+
+	/// The frames on a single line, with their source location if any,
+	/// e.g. for the tab separated output of the subcommands
+	pub fn one_line(&self) -> String {
+		let frames: Vec<_> = self
+			.0
+			.iter()
+			.map(|frame| match (&frame.file, frame.line) {
+				(Some(file), Some(line)) => format!("{} ({}:{})", frame.function, file, line),
+				_ => frame.function.clone(),
+			})
+			.collect();
+		frames.join(" -> ")
+	}
+}
+
+/// The chain of the functions on the first line, then a line for every frame
+/// with its source location
+impl fmt::Display for Backtrace {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let functions: Vec<_> = self.0.iter().map(|frame| frame.function.as_str()).collect();
+		write!(f, "wasm backtrace: {}", functions.join(" -> "))?;
+		for frame in &self.0 {
+			if let (Some(file), Some(line)) = (&frame.file, frame.line) {
+				write!(f, "\n    {} at {}:{}", frame.function, file, line)?;
+			}
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,250 @@
+//! Benchmarks of the phases of an execution and of the copies between the executor
+//! and the Linear Memory.
+//!
+//! Every phase is repeated on its own, so its cost is measured without the others:
+//! + engine: creation of the Engine and of the Linker with the Host Functions
+//! + compile: compilation of the wasm code into a Module, reading its DWARF if any as the Engine of
+//!   `Executor::new` does, see `Executor::pooled` for an Engine that does not
+//! + link: resolution of the imports, see `InstancePre`
+//! + instantiate: creation of the Store and instantiation
+//! + call: call of `start` on a new instance
+//!
+//! The Executor does the first three only once, an executor that rebuilds everything
+//! for every execution pays all of them.
+
+use crate::{
+	executor::{Executor, MAX_FUEL},
+	memory::{read_vec, write_vec, ForwardedMemory},
+	State,
+};
+use serde::Serialize;
+use std::{
+	fmt,
+	hint::black_box,
+	time::{Duration, Instant},
+};
+use wasmtime::*;
+
+/// Sizes of the vectors copied by the benchmarks of `read_vec` and `write_vec`
+pub const VEC_SIZES: [u32; 4] = [16, 1024, 16 * 1024, 64 * 1024];
+
+// Module calling the Host Function that benchmarks the copies, with enough memory
+// for the largest vector
+const MEMORY_BENCH_WAT: &str = r#"(module
+	(import "bench" "copies" (func $copies))
+	(memory (export "memory") 2)
+	(func (export "start") (call $copies)))"#;
+
+/// Minimum, median and 99th percentile of the measured durations, in nanoseconds
+#[derive(Serialize)]
+pub struct Stats {
+	pub min_ns: u64,
+	pub median_ns: u64,
+	pub p99_ns: u64,
+}
+
+impl Stats {
+	/// Statistics of the durations, the percentiles are nearest-rank: the smallest duration
+	/// greater than or equal to that percentage of the durations
+	pub fn new(mut durations: Vec<Duration>) -> Self {
+		durations.sort();
+		// Nearest-rank percentile
+		let percentile = |p: usize| {
+			let rank = (p * durations.len()).div_ceil(100).max(1);
+			durations.get(rank - 1).map_or(0, |duration| duration.as_nanos() as u64)
+		};
+		Stats { min_ns: percentile(0), median_ns: percentile(50), p99_ns: percentile(99) }
+	}
+}
+
+impl fmt::Display for Stats {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let us = |ns: u64| ns as f64 / 1000.0;
+		write!(f, "{:>12.3}{:>12.3}{:>12.3}", us(self.min_ns), us(self.median_ns), us(self.p99_ns))
+	}
+}
+
+/// The phases of the execution of a wasm code
+#[derive(Serialize)]
+pub struct BlobBench {
+	pub blob: String,
+	pub iterations: u32,
+	pub engine: Stats,
+	pub compile: Stats,
+	pub link: Stats,
+	pub instantiate: Stats,
+	pub call: Stats,
+}
+
+/// The copies of a vector between the executor and the Linear Memory
+#[derive(Serialize)]
+pub struct CopyBench {
+	pub size: u32,
+	pub read_vec: Stats,
+	pub write_vec: Stats,
+}
+
+// Measure `iterations` times the closure
+fn measure(
+	iterations: u32,
+	mut f: impl FnMut() -> Result<Duration, String>,
+) -> Result<Stats, String> {
+	let durations = (0..iterations).map(|_| f()).collect::<Result<_, _>>()?;
+	Ok(Stats::new(durations))
+}
+
+fn time<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+	let now = Instant::now();
+	let result = f();
+	(result, now.elapsed())
+}
+
+impl<S: State> Executor<S> {
+	/// Execute the blob `executions` times, every time over the same SharedState, and return
+	/// the time of all the executions. Used with `Executor::pooled` to measure its throughput.
+	pub fn throughput(
+		&mut self,
+		name: &str,
+		shared_state: &S,
+		executions: u32,
+	) -> Result<Duration, String> {
+		// The code could be changed since the last measure
+		self.refresh(name);
+		let now = Instant::now();
+		(0..executions).try_for_each(|_| self.execute(name, shared_state.clone()).map(|_| ()))?;
+		Ok(now.elapsed())
+	}
+
+	/// Benchmark the phases of the execution of a wasm blob over the SharedState,
+	/// every phase is repeated `iterations` times
+	pub fn bench(
+		&self,
+		name: &str,
+		shared_state: &S,
+		iterations: u32,
+	) -> Result<BlobBench, String> {
+		let wasm_code = self.wasm_codes().load(name).map_err(|err| err.to_string())?;
+		let registry = self.registry();
+
+		let engine = measure(iterations, || {
+			let (executor, elapsed) = time(|| {
+				let executor = Executor::new(self.wasm_codes().clone(), registry.clone())?;
+				// The Linker is created at its first use, not by `Executor::new`
+				executor.linker()?;
+				Ok::<_, String>(executor)
+			});
+			executor.map(|_| elapsed)
+		})?;
+
+		let compile_module = || Module::new(self.engine(), &wasm_code);
+		let compile = measure(iterations, || {
+			let (module, elapsed) = time(compile_module);
+			module.map(|_| elapsed).map_err(|err| err.to_string())
+		})?;
+
+		let module = compile_module().map_err(|err| err.to_string())?;
+		let linker = self.linker()?;
+		let link = measure(iterations, || {
+			let (instance_pre, elapsed) = time(|| linker.instantiate_pre(&module));
+			instance_pre.map(|_| elapsed).map_err(|err| err.to_string())
+		})?;
+
+		let instance_pre =
+			self.linker()?.instantiate_pre(&module).map_err(|err| err.to_string())?;
+		let new_instance = || -> Result<_> {
+			let mut store = Store::new(self.engine(), registry.store_data(shared_state.clone()));
+			store.add_fuel(MAX_FUEL)?;
+			let instance = instance_pre.instantiate(&mut store)?;
+			Ok((store, instance))
+		};
+		let instantiate = measure(iterations, || {
+			let (instance, elapsed) = time(new_instance);
+			instance.map(|_| elapsed).map_err(|err| err.to_string())
+		})?;
+
+		let call = measure(iterations, || {
+			let (mut store, instance) = new_instance().map_err(|err| err.to_string())?;
+			let (result, elapsed) = time(|| {
+				instance.get_typed_func::<(), ()>(&mut store, "start")?.call(&mut store, ())
+			});
+			result.map(|()| elapsed).map_err(|err| err.to_string())
+		})?;
+
+		Ok(BlobBench {
+			blob: name.to_string(),
+			iterations,
+			engine,
+			compile,
+			link,
+			instantiate,
+			call,
+		})
+	}
+}
+
+// The copies are called by a Host Function of the wasm code, its memory is never forwarded
+impl ForwardedMemory for Vec<CopyBench> {}
+
+/// Benchmark `read_vec` and `write_vec` with vectors of every size in `VEC_SIZES`,
+/// each copy is repeated `iterations` times
+pub fn bench_copies(iterations: u32) -> Result<Vec<CopyBench>, String> {
+	let engine = Engine::default();
+	let module = Module::new(&engine, MEMORY_BENCH_WAT).map_err(|err| err.to_string())?;
+
+	let mut linker = Linker::<Vec<CopyBench>>::new(&engine);
+	linker
+		.func_wrap("bench", "copies", move |mut caller: Caller<'_, Vec<CopyBench>>| -> Result<()> {
+			for size in VEC_SIZES {
+				let vec = vec![7; size as usize];
+				let (mut reads, mut writes) = (Vec::new(), Vec::new());
+				for _ in 0..iterations {
+					let (written, elapsed) = time(|| write_vec(&mut caller, 0, size, &vec));
+					black_box(written?);
+					writes.push(elapsed);
+
+					let (read, elapsed) = time(|| read_vec(&mut caller, 0, size));
+					black_box(read?);
+					reads.push(elapsed);
+				}
+				caller.data_mut().push(CopyBench {
+					size,
+					read_vec: Stats::new(reads),
+					write_vec: Stats::new(writes),
+				});
+			}
+			Ok(())
+		})
+		.map_err(|err| err.to_string())?;
+
+	let mut store = Store::new(&engine, Vec::new());
+	let instance = linker.instantiate(&mut store, &module).map_err(|err| err.to_string())?;
+	instance
+		.get_typed_func::<(), ()>(&mut store, "start")
+		.and_then(|start| start.call(&mut store, ()))
+		.map_err(|err| err.to_string())?;
+	Ok(store.into_data())
+}
+
+impl fmt::Display for BlobBench {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		writeln!(f, "Benchmark of {}, {} iterations (us)", self.blob, self.iterations)?;
+		write!(f, "{:<12}{:>12}{:>12}{:>12}", "phase", "min", "median", "p99")?;
+		for (phase, stats) in [
+			("engine", &self.engine),
+			("compile", &self.compile),
+			("link", &self.link),
+			("instantiate", &self.instantiate),
+			("call", &self.call),
+		] {
+			write!(f, "\n{:<12}{}", phase, stats)?;
+		}
+		Ok(())
+	}
+}
+
+impl fmt::Display for CopyBench {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		writeln!(f, "{:<12}{:<10}{}", "read_vec", self.size, self.read_vec)?;
+		write!(f, "{:<12}{:<10}{}", "write_vec", self.size, self.write_vec)
+	}
+}
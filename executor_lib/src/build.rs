@@ -0,0 +1,66 @@
+//! Build of a guest crate, e.g. `wasm_code`, into wasm codes for the executors.
+//!
+//! The crate is built with cargo for `wasm32-unknown-unknown` in release mode and the built
+//! wasm codes are found in the artifacts reported by cargo, wherever the target directory is.
+//!
+//! The guest crate can be a workspace, as `wasm_code` where every exercise is a crate: all the
+//! exercises are built together, or a single one is chosen by its package name, e.g. `ex4`.
+//! The exercises that compile are built even if another one fails, e.g. one not done yet.
+
+use serde::Deserialize;
+use std::{
+	path::{Path, PathBuf},
+	process::{Command, Stdio},
+};
+
+/// Target of the wasm codes
+pub const WASM_TARGET: &str = "wasm32-unknown-unknown";
+
+// The messages of cargo about the built artifacts, the others are ignored
+#[derive(Deserialize)]
+struct Message {
+	reason: String,
+	#[serde(default)]
+	filenames: Vec<PathBuf>,
+}
+
+/// Build the guest crate in `crate_dir`, or only its package `exercise`, and return the paths
+/// of the built wasm codes. It fails only if no wasm code is built, the errors of the
+/// compiler are printed on stderr.
+pub fn build(crate_dir: &Path, exercise: Option<&str>) -> Result<Vec<PathBuf>, String> {
+	let manifest = crate_dir.join("Cargo.toml");
+	if !manifest.is_file() {
+		return Err(format!("crate not found: {}", crate_dir.display()))
+	}
+
+	// `cargo run` sets the cargo that is running the executor
+	let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+	let mut command = Command::new(cargo);
+	command
+		.args(["build", "--release", "--keep-going", "--target", WASM_TARGET])
+		.args(["--message-format", "json-render-diagnostics", "--manifest-path"])
+		.arg(&manifest);
+	if let Some(exercise) = exercise {
+		command.args(["--package", exercise]);
+	}
+	let output = command
+		// The errors of the compiler are shown as they are
+		.stderr(Stdio::inherit())
+		.output()
+		.map_err(|err| format!("impossible run cargo: {}", err))?;
+
+	let stdout = String::from_utf8_lossy(&output.stdout);
+	let wasm_codes: Vec<_> = stdout
+		.lines()
+		.filter_map(|line| serde_json::from_str::<Message>(line).ok())
+		.filter(|message| message.reason == "compiler-artifact")
+		.flat_map(|message| message.filenames)
+		.filter(|path| path.extension().is_some_and(|extension| extension == "wasm"))
+		.collect();
+
+	match (output.status.success(), wasm_codes.is_empty()) {
+		(false, true) => Err("build failed".to_string()),
+		(true, true) => Err("no wasm code built, the crate type must be cdylib".to_string()),
+		(_, false) => Ok(wasm_codes),
+	}
+}
@@ -0,0 +1,533 @@
+//! Non-interactive interface of the executors, to be used from scripts and tests.
+//!
+//! Without a subcommand the interactive menu is started. Otherwise the subcommand is executed
+//! and only its results are printed to stdout, one per line with fields separated by tabs,
+//! while errors are printed to stderr and reported with the exit code. The newlines and the
+//! tabs inside a field, e.g. of an error, are escaped as `\n` and `\t`, see `field`.
+//!
+//! With `--json` every execution of a wasm code is instead reported as a JSON document
+//! on a single line, see `report::Report`.
+//!
+//! Every process starts from the SharedState of `--state`, or the initial one of the executor,
+//! while the name of the last wasm code executed is kept in the wasm codes directory, so
+//! `run-previous` executes it again also in a new process, even the code of an upgrade with
+//! `set_code` which is added to the wasm codes, see `Execution::upgrade`.
+
+use crate::{
+	abi,
+	backtrace::Backtrace,
+	bench, build,
+	environment::Provider,
+	executor::Executor,
+	pipeline,
+	profiler::Profiler,
+	report::Report,
+	server::{loopback_addr, HttpServer, Server},
+	wasm_codes::load_wasm_code,
+	State,
+};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::{fs, net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
+
+/// Exit code of a command that failed
+pub const EXIT_FAILURE: i32 = 1;
+/// Exit code of a command or a script that can't be parsed
+pub const EXIT_USAGE: i32 = 2;
+
+// Name of the last wasm code executed, in the wasm codes directory
+const PREVIOUS_FILE: &str = ".previous";
+
+#[derive(Parser)]
+#[command(
+	about = "Execute wasm blobs over a SharedState, without a subcommand the menu is started"
+)]
+pub struct Cli {
+	/// Initial value of the SharedState, in the same format printed by the executor
+	#[arg(long, global = true)]
+	pub state: Option<String>,
+	/// Print a JSON report for every execution of a wasm code
+	#[arg(long, global = true)]
+	pub json: bool,
+	/// Provider of the time and of the random bytes requested by the wasm code
+	#[arg(long, global = true, value_enum, default_value_t = EnvProvider::Real)]
+	pub env: EnvProvider,
+	/// Seed of the random bytes with `--env seeded`
+	#[arg(long, global = true, default_value_t = 0)]
+	pub seed: u64,
+	/// Time in milliseconds since the UNIX epoch with `--env fixed` or `--env seeded`
+	#[arg(long, global = true, default_value_t = 0)]
+	pub now: u64,
+	/// Provide the time and the random bytes recorded in a JSON report, instead of `--env`
+	#[arg(long, global = true)]
+	pub replay: Option<PathBuf>,
+	/// Reload the wasm codes changed while the menu is running
+	#[arg(long)]
+	pub watch: bool,
+	/// Also add the wasm codes built in this directory while the menu is running,
+	/// e.g. `../wasm_code/target/wasm32-unknown-unknown/release`. It implies `--watch`.
+	#[arg(long)]
+	pub watch_target: Option<PathBuf>,
+	#[command(subcommand)]
+	pub command: Option<Command>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum EnvProvider {
+	/// The system clock and the randomness of the operating system
+	Real,
+	/// Always the time `--now` and random bytes all zeros
+	Fixed,
+	/// Always the time `--now` and random bytes generated from `--seed`
+	Seeded,
+}
+
+impl Cli {
+	/// The Provider of the time and of the random bytes selected with `--env`,
+	/// or the one replaying the values recorded in the report of `--replay`
+	pub fn provider(&self) -> Result<Provider, String> {
+		if let Some(path) = &self.replay {
+			let report = fs::read_to_string(path).map_err(|err| err.to_string())?;
+			let report: serde_json::Value =
+				serde_json::from_str(&report).map_err(|err| err.to_string())?;
+			let recording = serde_json::from_value(report["environment"].clone())
+				.map_err(|_| format!("no recorded environment in {}", path.display()))?;
+			return Ok(Provider::Replay(Arc::new(recording)))
+		}
+
+		Ok(match self.env {
+			EnvProvider::Real => Provider::Real,
+			EnvProvider::Fixed => Provider::Fixed { now: self.now },
+			EnvProvider::Seeded => Provider::Seeded { now: self.now, seed: self.seed },
+		})
+	}
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+	/// Execute a wasm code and print the new SharedState
+	Run { name: String },
+	/// Execute again the previous wasm code, also the one of a previous process,
+	/// and print the new SharedState
+	RunPrevious,
+	/// Change the SharedState
+	SetState {
+		/// In the same format printed by the executor
+		value: String,
+	},
+	/// Print the names of the wasm codes, with their metadata
+	List,
+	/// Add a wasm code to the wasm codes, stored by its hash, and print the hash
+	Add {
+		/// Path of the wasm code, in the binary format
+		path: String,
+		/// Name of the wasm code, it identifies the last one added with it
+		#[arg(long)]
+		alias: Option<String>,
+		/// Version tag of the wasm code, identified by `alias:tag`
+		#[arg(long, requires = "alias")]
+		tag: Option<String>,
+	},
+	/// Build a guest crate for wasm32-unknown-unknown in release mode, add the built wasm codes
+	/// to the wasm codes and print their aliases and their hashes
+	Build {
+		/// Directory of the guest crate, or of a workspace of guest crates
+		#[arg(default_value = "../wasm_code")]
+		crate_dir: PathBuf,
+		/// Build only this package of the workspace, e.g. `ex4`
+		#[arg(long)]
+		exercise: Option<String>,
+		/// Alias of the wasm code, the name of the built file by default
+		#[arg(long)]
+		alias: Option<String>,
+		/// Version tag of the wasm codes, identified by `alias:tag`
+		#[arg(long)]
+		tag: Option<String>,
+	},
+	/// Print the hash, the size, the imports, the exports and the date a wasm code was added
+	Info { name: String },
+	/// Print the ABI version used by a wasm code
+	Abi { name: String },
+	/// Execute a pipeline of wasm codes and print the result of each stage
+	Pipeline {
+		/// Wasm code names separated by commas, or @ followed by a manifest file
+		stages: String,
+		/// Keep executing the stages after a failed one
+		#[arg(long)]
+		keep_going: bool,
+	},
+	/// Execute the commands in a script file, one per line
+	Script { path: String },
+	/// Execute a wasm code many times with the pooling allocator and print the executions
+	/// per second, the SharedState is not changed
+	Throughput {
+		name: String,
+		/// Number of executions, each one over the current SharedState
+		#[arg(long, default_value_t = 1000)]
+		executions: u32,
+	},
+	/// Execute a wasm code sampling its stack and timing the Host Functions, the SharedState
+	/// is not changed. The folded stacks and a Firefox Profiler profile are written.
+	Profile {
+		name: String,
+		/// Microseconds between two samples
+		#[arg(long, default_value_t = 100)]
+		interval_us: u64,
+		/// Directory of the profile files
+		#[arg(long, default_value = ".")]
+		output: String,
+	},
+	/// Benchmark the phases of the execution of a wasm code and the copies of vectors
+	/// between the executor and the Linear Memory, the SharedState is not changed
+	Bench {
+		name: String,
+		/// Repetitions of every phase and of every copy
+		#[arg(long, default_value_t = 100)]
+		iterations: u32,
+	},
+	/// Serve the JSON-RPC requests over HTTP to upload, list and execute the wasm codes,
+	/// see `server`
+	Serve {
+		/// Address to listen on, only a loopback address is accepted
+		#[arg(long, default_value = "127.0.0.1:8080", value_parser = loopback_addr)]
+		addr: SocketAddr,
+	},
+}
+
+/// A line of a script, it contains one of the subcommands
+#[derive(Parser)]
+#[command(no_binary_name = true)]
+struct ScriptLine {
+	#[command(subcommand)]
+	command: Command,
+}
+
+enum Error {
+	Failure(String),
+	Usage(String),
+}
+
+/// The state kept between the commands of a script
+pub struct Session<S: 'static> {
+	executor: Executor<S>,
+	state: S,
+	prev_executed_wasm_blob: Option<String>,
+	json: bool,
+}
+
+impl<S: State> Session<S> {
+	pub fn new(executor: Executor<S>, state: S, json: bool) -> Self {
+		Self { executor, state, prev_executed_wasm_blob: None, json }
+	}
+
+	/// Execute the command and return the exit code of the process
+	pub fn run(&mut self, command: Command) -> i32 {
+		match self.run_command(command) {
+			Ok(()) => 0,
+			Err(Error::Failure(err)) => {
+				eprintln!("Error: {}", err);
+				EXIT_FAILURE
+			},
+			Err(Error::Usage(err)) => {
+				eprintln!("Error: {}", err);
+				EXIT_USAGE
+			},
+		}
+	}
+
+	fn run_command(&mut self, command: Command) -> Result<(), Error> {
+		match command {
+			Command::Run { name } => {
+				// The wasm code could be changed since the last execution, e.g. in a script
+				self.executor.refresh(&name);
+				let upgrade = self.execute(&name)?;
+				self.set_previous(upgrade.unwrap_or(name));
+			},
+			Command::RunPrevious => {
+				let name =
+					self.previous().ok_or(Error::Failure("No previous wasm blob".to_string()))?;
+				let upgrade = self.execute(&name)?;
+				self.set_previous(upgrade.unwrap_or(name));
+			},
+			Command::SetState { value } => {
+				self.state = S::parse(&value).map_err(Error::Usage)?;
+				println!("{}", self.state.show());
+			},
+			Command::List =>
+				for name in self
+					.executor
+					.wasm_codes()
+					.list()
+					.map_err(|err| Error::Failure(err.to_string()))?
+				{
+					// The fields of the metadata follow the name, if the wasm code has them
+					match self.executor.metadata(&name) {
+						Ok(Some(metadata)) => println!(
+							"{}\t{}\t{}\tv{}",
+							name, metadata.name, metadata.version, metadata.abi
+						),
+						Ok(None) => println!("{}", name),
+						Err(err) => println!("{}\tinvalid metadata: {}", name, err),
+					}
+				},
+			Command::Add { path, alias, tag } => {
+				let wasm_code =
+					load_wasm_code(&path).map_err(|err| Error::Usage(err.to_string()))?;
+				let info = self
+					.executor
+					.wasm_codes()
+					.add(&wasm_code, alias.as_deref(), tag.as_deref())
+					.map_err(Error::Failure)?;
+				println!("{}", info.hash);
+			},
+			Command::Build { crate_dir, exercise, alias, tag } => {
+				let paths =
+					build::build(&crate_dir, exercise.as_deref()).map_err(Error::Failure)?;
+				if alias.is_some() && paths.len() > 1 {
+					return Err(Error::Usage(
+						"an alias names a single wasm code, choose it with --exercise".to_string(),
+					))
+				}
+				for path in paths {
+					let wasm_code =
+						load_wasm_code(&path).map_err(|err| Error::Failure(err.to_string()))?;
+					let alias = alias.clone().unwrap_or_else(|| {
+						path.file_stem().unwrap_or_default().to_string_lossy().into_owned()
+					});
+					let info = self
+						.executor
+						.wasm_codes()
+						.add(&wasm_code, Some(&alias), tag.as_deref())
+						.map_err(Error::Failure)?;
+					println!("{}\t{}", alias, info.hash);
+				}
+			},
+			Command::Info { name } => {
+				let info = self.executor.wasm_codes().info(&name).map_err(Error::Failure)?;
+				if self.json {
+					println!("{}", serde_json::to_string(&info).expect("the info is serializable"));
+				} else {
+					println!("hash\t{}", info.hash);
+					println!("size\t{}", info.size);
+					println!("imports\t{}", info.imports.join(","));
+					println!("exports\t{}", info.exports.join(","));
+					println!("added\t{}", info.added);
+				}
+			},
+			Command::Abi { name } => {
+				let version = self.executor.abi_version(&name).map_err(Error::Failure)?;
+				println!("{}", abi::show(version));
+			},
+			Command::Pipeline { stages, keep_going } => {
+				let stages = pipeline::parse_stages(&stages).map_err(Error::Usage)?;
+
+				let executor = &mut self.executor;
+				let mut reports = Vec::new();
+				let (new_state, report) = pipeline::run_pipeline(
+					&stages,
+					self.state.clone(),
+					keep_going,
+					|name, state| {
+						let input = state.clone();
+						let execution = executor.run(name, state);
+						// The error and the backtrace are two fields of the failed stage
+						let result = execution.result.clone().map_err(|err| {
+							let backtrace = execution.backtrace.as_ref();
+							let err = backtrace
+								.and_then(|backtrace| err.strip_suffix(&format!("\n{}", backtrace)))
+								.unwrap_or(&err);
+							let backtrace = backtrace.map(Backtrace::one_line).unwrap_or_default();
+							format!("{}\t{}", field(err), field(&backtrace))
+						});
+						reports.push(Report::from_execution(name, &input, execution));
+						result
+					},
+					S::show,
+				);
+				self.state = new_state;
+
+				let failed = report
+					.iter()
+					.any(|stage| matches!(stage.outcome, pipeline::Outcome::Failed(_)));
+
+				if self.json {
+					// Skipped stages are not executed, so they have no report
+					reports.iter().for_each(Report::print);
+				} else {
+					for stage in report {
+						match stage.outcome {
+							pipeline::Outcome::Succeeded(state) =>
+								println!("{}\tok\t{}", stage.name, field(&state)),
+							pipeline::Outcome::Failed(err) =>
+								println!("{}\tfailed\t{}", stage.name, err),
+							pipeline::Outcome::Skipped => println!("{}\tskipped", stage.name),
+						}
+					}
+				}
+				if failed {
+					return Err(Error::Failure("pipeline failed".to_string()))
+				}
+			},
+			Command::Throughput { name, executions } => {
+				let mut executor = Executor::pooled(
+					self.executor.wasm_codes().clone(),
+					self.executor.registry().clone(),
+				)
+				.map_err(Error::Failure)?;
+				let elapsed =
+					executor.throughput(&name, &self.state, executions).map_err(Error::Failure)?;
+				let per_second = executions as f64 / elapsed.as_secs_f64();
+
+				if self.json {
+					let report = serde_json::json!({
+						"blob": name,
+						"executions": executions,
+						"elapsed_us": elapsed.as_micros() as u64,
+						"per_second": per_second,
+					});
+					println!("{}", report);
+				} else {
+					println!("executions\t{}", executions);
+					println!("seconds\t{:.3}", elapsed.as_secs_f64());
+					println!("per_second\t{:.0}", per_second);
+				}
+			},
+			Command::Profile { name, interval_us, output } => {
+				let mut profiler = Profiler::new(
+					self.executor.wasm_codes().clone(),
+					self.executor.registry().clone(),
+					Duration::from_micros(interval_us),
+				)
+				.map_err(Error::Failure)?;
+
+				let input = self.state.clone();
+				let (execution, profile) = profiler.profile(&name, input.clone());
+				let result = execution.result.clone();
+
+				if self.json {
+					Report::from_execution(&name, &input, execution).print();
+				}
+				// A failed execution is profiled too, until the failure
+				if let Some(profile) = profile {
+					if !self.json {
+						println!("{}", profile);
+					}
+					for path in profile.write(&output).map_err(Error::Failure)? {
+						if !self.json {
+							println!("profile\t{}", path);
+						}
+					}
+				}
+				result.map_err(Error::Failure)?;
+			},
+			Command::Bench { name, iterations } => {
+				if iterations == 0 {
+					return Err(Error::Usage("at least one iteration is needed".to_string()))
+				}
+				let blob =
+					self.executor.bench(&name, &self.state, iterations).map_err(Error::Failure)?;
+				let copies = bench::bench_copies(iterations).map_err(Error::Failure)?;
+
+				if self.json {
+					let report = serde_json::json!({ "phases": blob, "copies": copies });
+					println!("{}", report);
+				} else {
+					println!("{}", blob);
+					println!(
+						"{:<12}{:<10}{:>12}{:>12}{:>12}",
+						"copy", "bytes", "min", "median", "p99"
+					);
+					copies.iter().for_each(|copy| println!("{}", copy));
+				}
+			},
+			Command::Serve { addr } => {
+				let server = Server::new(
+					self.executor.wasm_codes().clone(),
+					self.executor.registry().clone(),
+				)
+				.map_err(Error::Failure)?;
+				let http = HttpServer::bind(server, &addr.to_string()).map_err(Error::Failure)?;
+				if let Some(addr) = http.local_addr() {
+					println!("listening\t{}", addr);
+				}
+				http.serve();
+			},
+			Command::Script { path } => {
+				let script = fs::read_to_string(&path)
+					.map_err(|_| Error::Usage("script not found".to_string()))?;
+
+				// Stop at the first line that fails
+				for (index, line) in script.lines().enumerate() {
+					let line = line.trim();
+					if line.is_empty() || line.starts_with('#') {
+						continue
+					}
+
+					let command = ScriptLine::try_parse_from(line.split_whitespace())
+						.map_err(|err| {
+							// Only the first line of the clap error, without the usage
+							let err = err.to_string();
+							let err = err.lines().next().unwrap_or_default();
+							Error::Usage(format!(
+								"line {}: {}",
+								index + 1,
+								err.trim_start_matches("error: ")
+							))
+						})?
+						.command;
+					if let Command::Script { .. } = command {
+						return Err(Error::Usage(format!("line {}: nested script", index + 1)))
+					}
+
+					self.run_command(command).map_err(|err| match err {
+						Error::Failure(err) =>
+							Error::Failure(format!("line {}: {}", index + 1, err)),
+						Error::Usage(err) => Error::Usage(format!("line {}: {}", index + 1, err)),
+					})?;
+				}
+			},
+		}
+
+		Ok(())
+	}
+
+	// The last wasm code executed, by this process or by the previous one
+	fn previous(&self) -> Option<String> {
+		self.prev_executed_wasm_blob.clone().or_else(|| {
+			let path = self.executor.wasm_codes().dir().join(PREVIOUS_FILE);
+			fs::read_to_string(path).ok().filter(|name| !name.is_empty())
+		})
+	}
+
+	fn set_previous(&mut self, name: String) {
+		// If it can't be written `run-previous` works only in the same process, e.g. a script
+		let _ = fs::write(self.executor.wasm_codes().dir().join(PREVIOUS_FILE), &name);
+		self.prev_executed_wasm_blob = Some(name);
+	}
+
+	/// Execute the wasm code over the current SharedState and print the new one,
+	/// the name of the blob that replaced it with `set_code` is returned
+	fn execute(&mut self, name: &str) -> Result<Option<String>, Error> {
+		let input = self.state.clone();
+		let execution = self.executor.run(name, input.clone());
+		let result = execution.result.clone();
+		let upgrade = execution.upgrade.clone();
+
+		if self.json {
+			Report::from_execution(name, &input, execution).print();
+		}
+		self.state = result.map_err(Error::Failure)?;
+		if !self.json {
+			println!("{}", self.state.show());
+			if let Some(upgrade) = &upgrade {
+				println!("upgrade\t{}", upgrade);
+			}
+		}
+		Ok(upgrade)
+	}
+}
+
+/// A field of the tab separated output on a single line: the backslashes, the newlines and
+/// the tabs are escaped
+fn field(text: &str) -> String {
+	text.replace('\\', "\\\\").replace('\n', "\\n").replace('\t', "\\t")
+}
@@ -0,0 +1,178 @@
+//! Cryptographic Host Functions: hashing and signature verification.
+//!
+//! Hashing in wasm is slow, so the wasm code can ask the executor to do it.
+//! A hash function accepts the data (pointer and size) and the pointer of the area where
+//! the hash is written, a verification accepts the signature, the message (pointer and size)
+//! and the public key and returns 1 if the signature is valid, 0 otherwise.
+//! The signatures and the keys are read from fixed size areas, see `SIGNATURE_LEN`
+//! and `PUBLIC_KEY_LEN`.
+
+use crate::{
+	host::{HostModule, StoreData},
+	memory::{read_vec, write_vec},
+	weight::charge,
+};
+use blake2::{digest::consts::U32, Blake2b};
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+use std::hash::Hasher;
+use twox_hash::XxHash64;
+use wasmtime::*;
+
+/// Size of the ed25519 and sr25519 signatures
+pub const SIGNATURE_LEN: usize = 64;
+
+/// Size of the ed25519 and sr25519 public keys
+pub const PUBLIC_KEY_LEN: usize = 32;
+
+/// Signing context of the sr25519 signatures, the same used by Substrate
+pub const SR25519_CONTEXT: &[u8] = b"substrate";
+
+pub fn blake2b_256(data: &[u8]) -> [u8; 32] {
+	Blake2b::<U32>::digest(data).into()
+}
+
+pub fn sha2_256(data: &[u8]) -> [u8; 32] {
+	Sha256::digest(data).into()
+}
+
+pub fn keccak_256(data: &[u8]) -> [u8; 32] {
+	Keccak256::digest(data).into()
+}
+
+/// xxHash64 with seed 0, the bytes of the hash are little endian
+pub fn twox_64(data: &[u8]) -> [u8; 8] {
+	twox(data)
+}
+
+/// Two xxHash64 with seeds 0 and 1 concatenated, the same used by Substrate
+pub fn twox_128(data: &[u8]) -> [u8; 16] {
+	twox(data)
+}
+
+/// Four xxHash64 with seeds from 0 to 3 concatenated
+pub fn twox_256(data: &[u8]) -> [u8; 32] {
+	twox(data)
+}
+
+// The concatenation of `N / 8` xxHash64, each one with its index as seed
+fn twox<const N: usize>(data: &[u8]) -> [u8; N] {
+	let mut hash = [0; N];
+	for (seed, chunk) in hash.chunks_mut(8).enumerate() {
+		let mut hasher = XxHash64::with_seed(seed as u64);
+		hasher.write(data);
+		chunk.copy_from_slice(&hasher.finish().to_le_bytes());
+	}
+	hash
+}
+
+/// Verify an ed25519 signature, an invalid signature or public key is just not verified
+pub fn ed25519_verify(signature: &[u8], message: &[u8], public_key: &[u8]) -> bool {
+	use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+	let (Ok(signature), Ok(public_key)) = (signature.try_into(), public_key.try_into()) else {
+		return false
+	};
+	let Ok(public_key) = VerifyingKey::from_bytes(public_key) else { return false };
+	public_key.verify(message, &Signature::from_bytes(signature)).is_ok()
+}
+
+/// Verify an sr25519 signature with the signing context `SR25519_CONTEXT`
+pub fn sr25519_verify(signature: &[u8], message: &[u8], public_key: &[u8]) -> bool {
+	use schnorrkel::{PublicKey, Signature};
+
+	let (Ok(signature), Ok(public_key)) =
+		(Signature::from_bytes(signature), PublicKey::from_bytes(public_key))
+	else {
+		return false
+	};
+	public_key.verify_simple(SR25519_CONTEXT, message, &signature).is_ok()
+}
+
+/// The cryptographic Host Functions
+pub struct Crypto;
+
+impl Crypto {
+	// Define a hash Host Function: `name(data_ptr, data_len, out_ptr)`
+	fn add_hash<S: 'static, const N: usize>(
+		linker: &mut Linker<StoreData<S>>,
+		name: &'static str,
+		hash: fn(&[u8]) -> [u8; N],
+	) -> Result<()> {
+		linker.func_wrap(
+			"env_v1",
+			name,
+			move |mut caller: Caller<'_, StoreData<S>>,
+			      ptr: u32,
+			      size: u32,
+			      out_ptr: u32|
+			      -> Result<()> {
+				charge(&mut caller, ("env_v1", name), size as usize)?;
+				let data = read_vec(&mut caller, ptr, size)?;
+				write_vec(&mut caller, out_ptr, N as u32, &hash(&data))?;
+				Ok(())
+			},
+		)?;
+		Ok(())
+	}
+
+	// Define a verification Host Function:
+	// `name(signature_ptr, message_ptr, message_len, public_key_ptr) -> u32`
+	fn add_verify<S: 'static>(
+		linker: &mut Linker<StoreData<S>>,
+		name: &'static str,
+		verify: fn(&[u8], &[u8], &[u8]) -> bool,
+	) -> Result<()> {
+		linker.func_wrap(
+			"env_v1",
+			name,
+			move |mut caller: Caller<'_, StoreData<S>>,
+			      signature_ptr: u32,
+			      message_ptr: u32,
+			      message_len: u32,
+			      public_key_ptr: u32|
+			      -> Result<u32> {
+				// The signature and the public key have a fixed size, only the message is charged
+				charge(&mut caller, ("env_v1", name), message_len as usize)?;
+				let signature = read_vec(&mut caller, signature_ptr, SIGNATURE_LEN as u32)?;
+				let message = read_vec(&mut caller, message_ptr, message_len)?;
+				let public_key = read_vec(&mut caller, public_key_ptr, PUBLIC_KEY_LEN as u32)?;
+				Ok(verify(&signature, &message, &public_key) as u32)
+			},
+		)?;
+		Ok(())
+	}
+}
+
+impl<S: 'static> HostModule<S> for Crypto {
+	type Data = ();
+
+	fn namespace(&self) -> &'static str {
+		"env_v1"
+	}
+
+	fn functions(&self) -> &'static [&'static str] {
+		&[
+			"blake2b_256",
+			"sha2_256",
+			"keccak_256",
+			"twox_64",
+			"twox_128",
+			"twox_256",
+			"ed25519_verify",
+			"sr25519_verify",
+		]
+	}
+
+	fn register(&self, linker: &mut Linker<StoreData<S>>) -> Result<()> {
+		Self::add_hash(linker, "blake2b_256", blake2b_256)?;
+		Self::add_hash(linker, "sha2_256", sha2_256)?;
+		Self::add_hash(linker, "keccak_256", keccak_256)?;
+		Self::add_hash(linker, "twox_64", twox_64)?;
+		Self::add_hash(linker, "twox_128", twox_128)?;
+		Self::add_hash(linker, "twox_256", twox_256)?;
+		Self::add_verify(linker, "ed25519_verify", ed25519_verify)?;
+		Self::add_verify(linker, "sr25519_verify", sr25519_verify)?;
+		Ok(())
+	}
+}
@@ -0,0 +1,135 @@
+//! Host Functions giving the wasm code access to the time and to randomness.
+//!
+//! Both make an execution not reproducible, so they come from a configurable Provider:
+//! the real ones, fixed values or values generated from a seed. With the last two
+//! an execution can be replayed obtaining exactly the same result. The values provided
+//! to an execution are recorded in `Execution::environment`, with `Provider::Replay`
+//! they are served again, so also an execution with the real ones can be replayed.
+
+use crate::{
+	host::{HostModule, StoreData},
+	memory::write_vec,
+	weight::charge,
+};
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::{
+	sync::Arc,
+	time::{SystemTime, UNIX_EPOCH},
+};
+use wasmtime::*;
+
+// Maximum number of random bytes requested with a single call,
+// so the wasm code can't make the executor allocate too much memory
+const MAX_RANDOM_BYTES: u32 = 64 * 1024;
+
+/// Source of the time and of the randomness provided to the wasm code
+#[derive(Clone, Debug)]
+pub enum Provider {
+	/// The system clock and the randomness of the operating system
+	Real,
+	/// The time is always `now`, milliseconds since the UNIX epoch,
+	/// and the random bytes are all zeros
+	Fixed { now: u64 },
+	/// The time is always `now` and the random bytes are generated from the seed,
+	/// every execution starts again from the seed so it receives the same bytes
+	Seeded { now: u64, seed: u64 },
+	/// The values recorded during an execution, served again in the same order.
+	/// Every execution starts again from the first ones and fails if it requests more.
+	Replay(Arc<Recording>),
+}
+
+/// The values provided to an execution, in the order they were requested
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+	pub now_millis: Vec<u64>,
+	pub random_bytes: Vec<Vec<u8>>,
+}
+
+/// The values provided to an execution, with the generator of the random bytes of
+/// `Provider::Seeded`, created at the first request
+#[derive(Default)]
+pub struct EnvironmentData {
+	rng: Option<ChaCha8Rng>,
+	pub(crate) recording: Recording,
+}
+
+/// `now_millis()` returns the milliseconds since the UNIX epoch,
+/// `random_bytes(ptr, len)` writes `len` random bytes in the Linear Memory
+pub struct Environment(pub Provider);
+
+impl<S: 'static> HostModule<S> for Environment {
+	type Data = EnvironmentData;
+
+	fn namespace(&self) -> &'static str {
+		"env_v1"
+	}
+
+	fn functions(&self) -> &'static [&'static str] {
+		&["now_millis", "random_bytes"]
+	}
+
+	fn register(&self, linker: &mut Linker<StoreData<S>>) -> Result<()> {
+		let provider = self.0.clone();
+		linker.func_wrap("env_v1", "now_millis", move |mut caller: Caller<'_, StoreData<S>>| {
+			charge(&mut caller, ("env_v1", "now_millis"), 0)?;
+			let data = caller.data_mut().host_data_mut::<EnvironmentData>();
+			let now = match &provider {
+				Provider::Real => SystemTime::now()
+					.duration_since(UNIX_EPOCH)
+					.map(|now| now.as_millis() as u64)
+					.unwrap_or_default(),
+				Provider::Fixed { now } | Provider::Seeded { now, .. } => *now,
+				Provider::Replay(recording) => *recording
+					.now_millis
+					.get(data.recording.now_millis.len())
+					.ok_or_else(|| Error::msg("no more recorded values of now_millis"))?,
+			};
+			data.recording.now_millis.push(now);
+			Ok(now)
+		})?;
+
+		let provider = self.0.clone();
+
+		linker.func_wrap(
+			"env_v1",
+			"random_bytes",
+			move |mut caller: Caller<'_, StoreData<S>>, ptr: u32, size: u32| -> Result<()> {
+				if size > MAX_RANDOM_BYTES {
+					return Err(Trap::UnreachableCodeReached.into())
+				}
+				charge(&mut caller, ("env_v1", "random_bytes"), size as usize)?;
+
+				let data = caller.data_mut().host_data_mut::<EnvironmentData>();
+				let mut bytes = vec![0; size as usize];
+				match &provider {
+					Provider::Real => rand::thread_rng().fill_bytes(&mut bytes),
+					Provider::Fixed { .. } => (),
+					Provider::Seeded { seed, .. } => data
+						.rng
+						.get_or_insert_with(|| ChaCha8Rng::seed_from_u64(*seed))
+						.fill_bytes(&mut bytes),
+					Provider::Replay(recording) => {
+						bytes = recording
+							.random_bytes
+							.get(data.recording.random_bytes.len())
+							.filter(|recorded| recorded.len() == bytes.len())
+							.cloned()
+							.ok_or_else(|| {
+								Error::msg(format!(
+									"no more recorded random bytes of size {}",
+									size
+								))
+							})?;
+					},
+				}
+				data.recording.random_bytes.push(bytes.clone());
+				write_vec(&mut caller, ptr, size, &bytes)?;
+				Ok(())
+			},
+		)?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,582 @@
+use crate::{
+	abi,
+	backtrace::Backtrace,
+	environment::{EnvironmentData, Recording},
+	host::{Logs, Panic, ProposedCode, Registry, ReportedPanic, StoreData},
+	metadata::Metadata,
+	report::blob_hash,
+	wasm_codes::{WasmCodes, SHORT_HASH_LEN},
+	weight::Weight,
+	State,
+};
+use std::{
+	collections::HashMap,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		OnceLock,
+	},
+	time::{Duration, Instant},
+};
+use wasmtime::*;
+
+/// Result of an execution together with what has been observed during it
+pub struct Execution<S> {
+	/// The new SharedState or an Error containing a String describing the Error
+	pub result: Result<S, String>,
+	/// Set if the execution failed because of a trap
+	pub trap: Option<Trap>,
+	/// Hash of the executed wasm code, missing if it could not be loaded
+	pub blob_hash: Option<String>,
+	/// ABI version used by the executed wasm code, see `abi::blob_version`
+	pub abi_version: Option<u32>,
+	/// Missing if the wasm code was not executed
+	pub fuel_consumed: Option<u64>,
+	/// The fuel consumed split between the wasm instructions and the Host Functions,
+	/// missing if the wasm code was not executed
+	pub weight: Option<Weight>,
+	pub logs: Vec<String>,
+	/// The panic reported by the wasm code before trapping, its message is also in the error
+	pub panic: Option<Panic>,
+	/// The wasm frames of a failed execution, they are also in the error
+	pub backtrace: Option<Backtrace>,
+	/// Name of the blob loaded from the code proposed with `set_code`, it replaces the
+	/// executed one. The code is added to the wasm codes with the name as alias.
+	pub upgrade: Option<String>,
+	/// The time and the random bytes provided to the wasm code, missing if the
+	/// `Environment` host module is not registered or the wasm code was not executed
+	pub environment: Option<Recording>,
+	pub elapsed: Duration,
+}
+
+/// Number of instance slots preallocated by the pooling allocator of `Executor::pooled`,
+/// the executor runs one blob at a time so a few slots are enough
+pub const POOL_INSTANCE_COUNT: u32 = 16;
+
+/// Maximum number of linear memory pages (64KiB each) of every pooled instance
+pub const POOL_MEMORY_PAGES: u64 = 160;
+
+// Fuel provided to each execution, every wasm instruction consumes some fuel
+// and the execution traps when there is no more, so even an infinite loop terminates
+pub(crate) const MAX_FUEL: u64 = 1_000_000_000;
+
+/// Resources available to an execution, it traps when it exceeds them
+#[derive(Clone, Copy, Debug)]
+pub struct Limits {
+	/// Fuel provided to the execution, see `Execution::weight`
+	pub fuel: u64,
+	/// Maximum size in bytes of the Linear Memory, growing it beyond traps.
+	/// The default is unlimited.
+	pub memory_bytes: Option<usize>,
+}
+
+impl Default for Limits {
+	fn default() -> Self {
+		Limits { fuel: MAX_FUEL, memory_bytes: None }
+	}
+}
+
+impl Limits {
+	// Limiter of the Store enforcing the limit on the Linear Memory
+	fn store_limits(&self) -> StoreLimits {
+		match self.memory_bytes {
+			Some(memory_bytes) => StoreLimitsBuilder::new()
+				.memory_size(memory_bytes)
+				.trap_on_grow_failure(true)
+				.build(),
+			None => StoreLimits::default(),
+		}
+	}
+}
+
+/// The steps of an execution left to the executor: creating the Store and instantiating
+/// the blob, already linked to the Host Functions, to call its entry point.
+///
+/// `DefaultSteps` are used if the Registry does not provide others, see `Registry::with_steps`.
+/// The AsyncExecutor instantiates the blobs asynchronously, it uses only `new_store`.
+pub trait ExecutionSteps<S>: Send + Sync + 'static {
+	/// Create the Store of an execution containing the StoreData with the SharedState
+	fn new_store(&self, engine: &Engine, data: StoreData<S>) -> Store<StoreData<S>>;
+
+	/// Instantiate the linked blob in the Store and execute its entry point `start`
+	fn call_start(
+		&self,
+		instance_pre: &InstancePre<StoreData<S>>,
+		store: &mut Store<StoreData<S>>,
+	) -> Result<()>;
+}
+
+/// The steps of the executors that do not implement them
+pub struct DefaultSteps;
+
+impl<S> ExecutionSteps<S> for DefaultSteps {
+	fn new_store(&self, engine: &Engine, data: StoreData<S>) -> Store<StoreData<S>> {
+		Store::new(engine, data)
+	}
+
+	fn call_start(
+		&self,
+		instance_pre: &InstancePre<StoreData<S>>,
+		store: &mut Store<StoreData<S>>,
+	) -> Result<()> {
+		// Instantiate the wasm code, no need to link it again
+		let instance = instance_pre.instantiate(&mut *store)?;
+
+		// Extract the entry point "start" end execute it
+		let start = instance.get_typed_func::<(), ()>(&mut *store, "start")?;
+
+		start.call(store, ())
+	}
+}
+
+/// A wasm blob compiled and already linked to the Host Functions.
+///
+/// The imports are resolved only once, when the blob is loaded,
+/// so a missing or wrong Host Function is reported at load time.
+/// Each execution just needs a new Store to instantiate it.
+pub(crate) struct LoadedBlob<S: 'static> {
+	instance_pre: InstancePre<StoreData<S>>,
+	pub(crate) hash: String,
+	abi_version: Option<u32>,
+}
+
+// InstancePre is cheap to clone, it shares the compiled Module
+impl<S> Clone for LoadedBlob<S> {
+	fn clone(&self) -> Self {
+		LoadedBlob {
+			instance_pre: self.instance_pre.clone(),
+			hash: self.hash.clone(),
+			abi_version: self.abi_version,
+		}
+	}
+}
+
+/// The Executor of the wasm blobs over a SharedState `S`
+///
+/// The Engine and the Linker with the Host Functions are created once,
+/// and the loaded blobs are cached by name to be executed again
+/// without compiling and linking them every time.
+///
+/// The Linker is created when the first blob is loaded, so an executor whose Host Functions
+/// can't be registered, e.g. not implemented yet, still lists the wasm codes.
+pub struct Executor<S: 'static> {
+	engine: Engine,
+	linker: OnceLock<Result<Linker<StoreData<S>>, String>>,
+	registry: Registry<S>,
+	wasm_codes: WasmCodes,
+	blobs: HashMap<String, LoadedBlob<S>>,
+	compilations: AtomicU64,
+}
+
+impl<S: State> Executor<S> {
+	/// Executor using the default Engine
+	pub fn new(wasm_codes: WasmCodes, registry: Registry<S>) -> Result<Self, String> {
+		// Global compilation environment for WebAssembly
+		Self::with_engine(backtrace_config(), wasm_codes, registry)
+	}
+
+	/// Executor optimized for the execution of the same blob many times.
+	///
+	/// The Engine is configured with the pooling instance allocator:
+	/// the memory for the instances is reserved once in preallocated slots and reused,
+	/// and the linear memory of each new instance is initialized copy-on-write from the
+	/// module image instead of copying the data segments every time. The DWARF of the wasm
+	/// codes is not read, so the compilation is faster and the backtraces have no source lines.
+	pub fn pooled(wasm_codes: WasmCodes, registry: Registry<S>) -> Result<Self, String> {
+		let mut pooling_config = PoolingAllocationConfig::default();
+		pooling_config
+			.instance_count(POOL_INSTANCE_COUNT)
+			.instance_memory_pages(POOL_MEMORY_PAGES);
+
+		let mut config = Config::new();
+		config
+			.allocation_strategy(InstanceAllocationStrategy::Pooling(pooling_config))
+			.memory_init_cow(true)
+			.wasm_backtrace_details(WasmBacktraceDetails::Disable);
+
+		Self::with_engine(config, wasm_codes, registry)
+	}
+
+	pub(crate) fn with_engine(
+		mut config: Config,
+		wasm_codes: WasmCodes,
+		registry: Registry<S>,
+	) -> Result<Self, String> {
+		// Count the instructions executed by the wasm code
+		config.consume_fuel(true);
+		let engine = Engine::new(&config).map_err(|err| err.to_string())?;
+
+		Ok(Self {
+			engine,
+			linker: OnceLock::new(),
+			registry,
+			wasm_codes,
+			blobs: HashMap::new(),
+			compilations: AtomicU64::new(0),
+		})
+	}
+
+	/// Executor sharing the Engine and the Linker, if already created, without the loaded blobs.
+	/// The blobs it loads can be executed by this one, see `insert`.
+	pub(crate) fn fork(&self) -> Self {
+		Self {
+			engine: self.engine.clone(),
+			linker: self.linker.clone(),
+			registry: self.registry.clone(),
+			wasm_codes: self.wasm_codes.clone(),
+			blobs: HashMap::new(),
+			compilations: AtomicU64::new(0),
+		}
+	}
+
+	pub fn wasm_codes(&self) -> &WasmCodes {
+		&self.wasm_codes
+	}
+
+	pub fn registry(&self) -> &Registry<S> {
+		&self.registry
+	}
+
+	/// Number of wasm codes compiled by the executor, a loaded blob is executed again
+	/// without compiling it
+	pub fn compilations(&self) -> u64 {
+		self.compilations.load(Ordering::Relaxed)
+	}
+
+	pub(crate) fn engine(&self) -> &Engine {
+		&self.engine
+	}
+
+	/// The Linker will link the Host Functions of all the host modules
+	/// to the wasm code on instantiation time, it is created the first time it is needed
+	pub(crate) fn linker(&self) -> Result<&Linker<StoreData<S>>, String> {
+		self.linker
+			.get_or_init(|| self.registry.linker(&self.engine))
+			.as_ref()
+			.map_err(|err| err.clone())
+	}
+
+	/// Compile the wasm blob and resolve its imports,
+	/// a blob already loaded with the same name is replaced.
+	pub fn load(&mut self, name: &str) -> Result<(), String> {
+		let blob = self.load_blob(name)?;
+		self.blobs.insert(name.to_string(), blob);
+		Ok(())
+	}
+
+	/// Compile and link the wasm blob without caching it
+	pub(crate) fn load_blob(&self, name: &str) -> Result<LoadedBlob<S>, String> {
+		// Firstly the wasm code is neeed
+		let wasm_code = self.wasm_codes.load(name).map_err(|err| err.to_string())?;
+		self.compile(wasm_code)
+	}
+
+	pub(crate) fn compile(&self, wasm_code: Vec<u8>) -> Result<LoadedBlob<S>, String> {
+		let hash = blob_hash(&wasm_code);
+		let metadata = Metadata::parse(&wasm_code)?;
+
+		// Compile the Wasm code into a Module,
+		// the in-memory JIT code which is ready
+		// to be execute after being instantiated
+		let module = Module::new(&self.engine, wasm_code).map_err(|err| err.to_string())?;
+		self.compilations.fetch_add(1, Ordering::Relaxed);
+
+		// A typed handle to "start" is bound to the Store it is extracted from,
+		// so it can't be cached, but its signature can be checked only once here
+		match module.get_export("start") {
+			Some(ExternType::Func(ty)) if ty.params().len() == 0 && ty.results().len() == 0 => (),
+			_ => return Err("missing entry point: fn start()".to_string()),
+		}
+
+		// Refuse the wasm code if its metadata require an ABI version that is not supported
+		let abi_version = abi::blob_version(&module);
+		if let Some(metadata) = metadata {
+			metadata.check_abi(abi_version, self.registry.latest_abi_version())?;
+		}
+
+		// Resolve the imports with the Host Functions,
+		// the ones of an older ABI version are provided by the compatibility layer
+		let instance_pre =
+			self.linker()?.instantiate_pre(&module).map_err(|err| err.to_string())?;
+
+		Ok(LoadedBlob { instance_pre, hash, abi_version })
+	}
+
+	/// Validate the code proposed by the blob `name` with `set_code` and load it,
+	/// the name of the new blob is returned.
+	fn upgrade(&mut self, name: &str, wasm_code: Vec<u8>) -> Result<String, String> {
+		let blob = self.compile(wasm_code.clone())?;
+
+		let upgraded_name = self.add_upgrade(name, &wasm_code, &blob.hash)?;
+		self.blobs.insert(upgraded_name.clone(), blob);
+		Ok(upgraded_name)
+	}
+
+	/// Add the validated code proposed by the blob `name` to the wasm codes, so it can be
+	/// loaded by any executor and process. Its alias is named after the upgraded blob and
+	/// the hash of the new code, see `upgraded_name`, and it is returned.
+	pub(crate) fn add_upgrade(
+		&self,
+		name: &str,
+		wasm_code: &[u8],
+		hash: &str,
+	) -> Result<String, String> {
+		let upgraded_name = upgraded_name(name, hash);
+		self.wasm_codes.add(wasm_code, Some(&upgraded_name), None)?;
+		Ok(upgraded_name)
+	}
+
+	/// Metadata of a wasm code, read without compiling it
+	pub fn metadata(&self, name: &str) -> Result<Option<Metadata>, String> {
+		let wasm_code = self.wasm_codes.load(name).map_err(|err| err.to_string())?;
+		Metadata::parse(&wasm_code)
+	}
+
+	/// ABI version used by a wasm blob, the blob is loaded if it is not already
+	pub fn abi_version(&mut self, name: &str) -> Result<Option<u32>, String> {
+		self.ensure_loaded(name)?;
+		Ok(self.blobs[name].abi_version)
+	}
+
+	/// Replace the loaded blob with the same name, the blob must have been loaded
+	/// by this Executor or by a fork
+	pub(crate) fn insert(&mut self, name: &str, blob: LoadedBlob<S>) {
+		self.blobs.insert(name.to_string(), blob);
+	}
+
+	/// Forget a loaded blob, the next execution will load it again
+	pub fn unload(&mut self, name: &str) {
+		self.blobs.remove(name);
+	}
+
+	/// Forget a loaded blob whose wasm code changed since it was loaded, e.g. rebuilt or
+	/// added again with the same alias. The wasm code is only read and hashed, the blob is
+	/// compiled again by the next execution only if the hash is different.
+	pub fn refresh(&mut self, name: &str) {
+		let Some(blob) = self.blobs.get(name) else { return };
+		match self.wasm_codes.load(name) {
+			Ok(wasm_code) if blob_hash(&wasm_code) == blob.hash => (),
+			_ => self.unload(name),
+		}
+	}
+
+	/// Execute a wasm blob over the SharedState, the blob is loaded if it is not already.
+	///
+	/// The Result is the new SharedState or an Error containing a String describing the Error
+	pub fn execute(&mut self, name: &str, shared_state: S) -> Result<S, String> {
+		self.run(name, shared_state).result
+	}
+
+	/// Same as `execute` but everything observed during the execution is returned
+	pub fn run(&mut self, name: &str, shared_state: S) -> Execution<S> {
+		let steps = self.registry.steps().clone();
+		self.run_with(name, shared_state, move |_, instance_pre, store| {
+			steps.call_start(instance_pre, store)
+		})
+	}
+
+	/// Execute a wasm blob calling its entry point with `call`, which receives the Linker,
+	/// the linked blob and the Store, e.g. to instrument the execution
+	pub(crate) fn run_with(
+		&mut self,
+		name: &str,
+		shared_state: S,
+		call: impl FnOnce(
+			&Linker<StoreData<S>>,
+			&InstancePre<StoreData<S>>,
+			&mut Store<StoreData<S>>,
+		) -> Result<()>,
+	) -> Execution<S> {
+		let now = Instant::now();
+
+		if let Err(err) = self.ensure_loaded(name) {
+			return Execution::failed(err, now.elapsed())
+		}
+
+		let (execution, proposed_code) =
+			self.execute_blob(&self.blobs[name], shared_state, &Limits::default(), call);
+		self.finish(name, execution, proposed_code, now)
+	}
+
+	/// Same as `run` but `start` is driven as a future, for an Engine with async support.
+	///
+	/// The wasm code yields at every epoch, so the Engine must have epoch interruption
+	/// enabled and its epoch must be incremented, see `AsyncExecutor`.
+	pub(crate) async fn run_async(&mut self, name: &str, shared_state: S) -> Execution<S>
+	where
+		S: Send,
+	{
+		let now = Instant::now();
+
+		if let Err(err) = self.ensure_loaded(name) {
+			return Execution::failed(err, now.elapsed())
+		}
+
+		let blob = &self.blobs[name];
+		let mut store = self.new_store(shared_state, &Limits::default());
+		store.epoch_deadline_async_yield_and_update(1);
+		let result = call_start_async(&blob.instance_pre, &mut store).await;
+
+		let (execution, proposed_code) = self.observe(blob, store, result);
+		self.finish(name, execution, proposed_code, now)
+	}
+
+	fn ensure_loaded(&mut self, name: &str) -> Result<(), String> {
+		if !self.blobs.contains_key(name) {
+			self.load(name)?;
+		}
+		Ok(())
+	}
+
+	// Validate the code proposed by the blob `name` and measure the whole execution
+	fn finish(
+		&mut self,
+		name: &str,
+		mut execution: Execution<S>,
+		proposed_code: Option<Vec<u8>>,
+		started: Instant,
+	) -> Execution<S> {
+		// The new code proposed with `set_code` is validated only after a successful
+		// execution, if it is not valid the whole execution fails
+		if let (Ok(_), Some(wasm_code)) = (&execution.result, proposed_code) {
+			match self.upgrade(name, wasm_code) {
+				Ok(upgraded_name) => execution.upgrade = Some(upgraded_name),
+				Err(err) => execution.result = Err(format!("invalid code upgrade: {}", err)),
+			}
+		}
+
+		execution.elapsed = started.elapsed();
+		execution
+	}
+
+	/// Execute a loaded blob in a new Store within the limits, calling its entry point with
+	/// `call` like `run_with`. The code proposed with `set_code` is returned to be validated
+	/// by the caller, the elapsed time is not measured.
+	pub(crate) fn execute_blob(
+		&self,
+		blob: &LoadedBlob<S>,
+		shared_state: S,
+		limits: &Limits,
+		call: impl FnOnce(
+			&Linker<StoreData<S>>,
+			&InstancePre<StoreData<S>>,
+			&mut Store<StoreData<S>>,
+		) -> Result<()>,
+	) -> (Execution<S>, Option<Vec<u8>>) {
+		let linker = self.linker().expect("the Linker has linked the loaded blob");
+		let mut store = self.new_store(shared_state, limits);
+		let result = call(linker, &blob.instance_pre, &mut store);
+		self.observe(blob, store, result)
+	}
+
+	// A new Store for an execution over the SharedState within the limits
+	fn new_store(&self, shared_state: S, limits: &Limits) -> Store<StoreData<S>> {
+		// The Store will contain all the information related to
+		// WebAssembly objects such as functions, instances, memories, etc
+		let data = self.registry.store_data(shared_state);
+		let mut store = self.registry.steps().new_store(&self.engine, data);
+		store.add_fuel(limits.fuel).expect("fuel consumption is enabled in the Engine");
+		store.data_mut().limits = limits.store_limits();
+		store.limiter(|data| &mut data.limits);
+		store
+	}
+
+	// What has been observed during the execution of the blob that returned `result`,
+	// and the code it proposed
+	fn observe(
+		&self,
+		blob: &LoadedBlob<S>,
+		store: Store<StoreData<S>>,
+		result: Result<()>,
+	) -> (Execution<S>, Option<Vec<u8>>) {
+		let fuel_consumed = store.fuel_consumed();
+		let trap = result.as_ref().err().and_then(|err| err.downcast_ref::<Trap>().copied());
+
+		// Just return the new SharedState,
+		// the Store is consumed so there is no need to clone it
+		let mut data = store.into_data();
+		let logs = data.take_host_data::<Logs>().map(|logs| logs.0).unwrap_or_default();
+		let proposed_code = data.take_host_data::<ProposedCode>().and_then(|code| code.0);
+		let panic = data.take_host_data::<ReportedPanic>().and_then(|panic| panic.0);
+		let environment = data.take_host_data::<EnvironmentData>().map(|data| data.recording);
+		let weight = fuel_consumed.map(|fuel| data.meter.weight(fuel));
+		let backtrace = result
+			.as_ref()
+			.err()
+			.and_then(|err| Backtrace::from_error(err, panic.is_some()));
+		let result = result.map(|()| data.shared_state).map_err(|err| {
+			// A panic ends with an `unreachable` trap, the error starts with the reported panic.
+			// The backtrace is the symbolicated one, not the raw one of wasmtime
+			let err = match (&panic, trap, &backtrace) {
+				(Some(panic), Some(trap), _) => format!("{} ({})", panic, trap),
+				(_, _, Some(_)) => err.root_cause().to_string(),
+				_ => err.to_string(),
+			};
+			match &backtrace {
+				Some(backtrace) => format!("{}\n{}", err, backtrace),
+				None => err,
+			}
+		});
+
+		let execution = Execution {
+			result,
+			trap,
+			blob_hash: Some(blob.hash.clone()),
+			abi_version: blob.abi_version,
+			fuel_consumed,
+			weight,
+			logs,
+			panic,
+			backtrace,
+			upgrade: None,
+			environment,
+			elapsed: Duration::ZERO,
+		};
+		(execution, proposed_code)
+	}
+}
+
+impl<S> Execution<S> {
+	/// Execution of a wasm code that could not be loaded
+	pub(crate) fn failed(err: String, elapsed: Duration) -> Self {
+		Execution {
+			result: Err(err),
+			trap: None,
+			blob_hash: None,
+			abi_version: None,
+			fuel_consumed: None,
+			weight: None,
+			logs: Vec::new(),
+			panic: None,
+			backtrace: None,
+			upgrade: None,
+			environment: None,
+			elapsed,
+		}
+	}
+}
+
+/// Configuration of the engines reading the DWARF of the wasm codes, if any,
+/// to symbolicate their backtraces with the source lines
+pub(crate) fn backtrace_config() -> Config {
+	let mut config = Config::new();
+	config.wasm_backtrace_details(WasmBacktraceDetails::Enable);
+	config
+}
+
+/// Name of the blob loaded from the code proposed by the blob `name`, named after it and the
+/// hash of the new code, e.g. `mul_vec_3@3932109ef795`. The version tag and the extension
+/// of the name are dropped, so the name is a valid alias.
+pub(crate) fn upgraded_name(name: &str, hash: &str) -> String {
+	let base_name = name.split(['@', ':']).next().unwrap_or(name);
+	let base_name = base_name.strip_suffix(".wasm").unwrap_or(base_name);
+	format!("{}@{}", base_name, &hash[..SHORT_HASH_LEN])
+}
+
+async fn call_start_async<S: Send>(
+	instance_pre: &InstancePre<StoreData<S>>,
+	store: &mut Store<StoreData<S>>,
+) -> Result<()> {
+	let instance = instance_pre.instantiate_async(&mut *store).await?;
+	let start = instance.get_typed_func::<(), ()>(&mut *store, "start")?;
+	start.call_async(store, ()).await
+}
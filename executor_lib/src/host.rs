@@ -0,0 +1,393 @@
+//! Host Functions provided to the wasm blobs, grouped in host modules.
+//!
+//! A host module declares the namespace of its imports, the names of its functions
+//! and the data it needs in the Store of every execution. The Registry composes
+//! several host modules into the Linker used by the Executor, so a new host API
+//! is just a new `HostModule` added to the Registry.
+//!
+//! The host modules of an ABI version use its namespace, see `abi`. The Registry adds
+//! a compatibility layer so the wasm blobs compiled against an older version keep running:
+//! every version contains the Host Functions of the previous one that it does not redefine,
+//! and the legacy `env` namespace contains the Host Functions of `env_v1`.
+
+use crate::{
+	abi,
+	executor::{DefaultSteps, ExecutionSteps},
+	memory::{read_vec, ForwardedMemory},
+	weight::{charge, HostCost, HostFunction, Meter},
+};
+use serde::Serialize;
+use std::{
+	any::{Any, TypeId},
+	collections::HashMap,
+	fmt,
+	panic::{self, AssertUnwindSafe},
+	sync::Arc,
+};
+use wasmtime::*;
+
+/// Data contained in the Store during an execution,
+/// besides the SharedState it contains the data of every host module
+pub struct StoreData<S> {
+	pub shared_state: S,
+	host_data: HashMap<TypeId, Box<dyn Any + Send>>,
+	pub(crate) meter: Meter,
+	pub(crate) limits: StoreLimits,
+	/// Linear Memory of the wasm code during a call of a Host Function through another one
+	pub(crate) forwarded_memory: Option<Memory>,
+}
+
+impl<S> StoreData<S> {
+	/// The data of the host module with `T` as `HostModule::Data`
+	pub fn host_data<T: 'static>(&self) -> &T {
+		self.host_data
+			.get(&TypeId::of::<T>())
+			.and_then(|data| data.downcast_ref())
+			.expect("the data of the registered host modules is created with the Store")
+	}
+
+	/// Same as `host_data` but mutable
+	pub fn host_data_mut<T: 'static>(&mut self) -> &mut T {
+		self.host_data
+			.get_mut(&TypeId::of::<T>())
+			.and_then(|data| data.downcast_mut())
+			.expect("the data of the registered host modules is created with the Store")
+	}
+
+	/// Remove the data of a host module, missing if the host module is not registered
+	pub(crate) fn take_host_data<T: 'static>(&mut self) -> Option<T> {
+		let data = self.host_data.remove(&TypeId::of::<T>())?;
+		data.downcast().ok().map(|data| *data)
+	}
+}
+
+impl<S> ForwardedMemory for StoreData<S> {
+	fn forwarded_memory(&self) -> Option<Memory> {
+		self.forwarded_memory
+	}
+}
+
+/// A group of Host Functions imported by the wasm blobs from the same namespace
+pub trait HostModule<S>: Send + Sync + 'static {
+	/// Data needed by the Host Functions, a new one is created in the Store of every
+	/// execution and it is accessible with `StoreData::host_data`
+	type Data: Default + Send + 'static;
+
+	/// Namespace of the imports, `env_v1`, `env_v2`, ... for the Host Functions of an ABI version
+	fn namespace(&self) -> &'static str;
+
+	/// Names of the Host Functions defined by `register`
+	fn functions(&self) -> &'static [&'static str];
+
+	/// Define the Host Functions in the Linker
+	fn register(&self, linker: &mut Linker<StoreData<S>>) -> Result<()>;
+}
+
+// `HostModule` without the associated type, so the Registry can contain different ones
+trait AnyHostModule<S>: Send + Sync {
+	fn namespace(&self) -> &'static str;
+	fn functions(&self) -> &'static [&'static str];
+	fn register(&self, linker: &mut Linker<StoreData<S>>) -> Result<()>;
+	fn insert_data(&self, host_data: &mut HashMap<TypeId, Box<dyn Any + Send>>);
+}
+
+impl<S, M: HostModule<S>> AnyHostModule<S> for M {
+	fn namespace(&self) -> &'static str {
+		HostModule::namespace(self)
+	}
+
+	fn functions(&self) -> &'static [&'static str] {
+		HostModule::functions(self)
+	}
+
+	fn register(&self, linker: &mut Linker<StoreData<S>>) -> Result<()> {
+		HostModule::register(self, linker)
+	}
+
+	fn insert_data(&self, host_data: &mut HashMap<TypeId, Box<dyn Any + Send>>) {
+		host_data.insert(TypeId::of::<M::Data>(), Box::<M::Data>::default());
+	}
+}
+
+/// The host modules provided to the wasm blobs, with the costs of their Host Functions
+/// and the steps of the executions, see `ExecutionSteps`
+pub struct Registry<S> {
+	modules: Vec<Arc<dyn AnyHostModule<S>>>,
+	costs: Arc<HashMap<HostFunction, HostCost>>,
+	steps: Arc<dyn ExecutionSteps<S>>,
+}
+
+impl<S> Clone for Registry<S> {
+	fn clone(&self) -> Self {
+		Self { modules: self.modules.clone(), costs: self.costs.clone(), steps: self.steps.clone() }
+	}
+}
+
+impl<S: 'static> Default for Registry<S> {
+	fn default() -> Self {
+		Self { modules: Vec::new(), costs: Arc::default(), steps: Arc::new(DefaultSteps) }
+	}
+}
+
+impl<S: 'static> Registry<S> {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Add a host module, its Host Functions are defined in the Linker of every Executor
+	pub fn with(mut self, module: impl HostModule<S>) -> Self {
+		self.modules.push(Arc::new(module));
+		self
+	}
+
+	/// Set the cost of Host Functions, identified by namespace and name, charged against the
+	/// fuel by `weight::charge`. The Host Functions without a cost are free.
+	pub fn with_costs(mut self, costs: &[(HostFunction, HostCost)]) -> Self {
+		Arc::make_mut(&mut self.costs).extend(costs.iter().copied());
+		self
+	}
+
+	/// Create the Store and call the entry point of the blobs with the steps implemented by
+	/// the executor, instead of `DefaultSteps`
+	pub fn with_steps(mut self, steps: impl ExecutionSteps<S>) -> Self {
+		self.steps = Arc::new(steps);
+		self
+	}
+
+	pub(crate) fn steps(&self) -> &Arc<dyn ExecutionSteps<S>> {
+		&self.steps
+	}
+
+	/// Namespace and functions of every host module, in the order they were added
+	pub fn modules(&self) -> impl Iterator<Item = (&'static str, &'static [&'static str])> + '_ {
+		self.modules.iter().map(|module| (module.namespace(), module.functions()))
+	}
+
+	/// The latest ABI version among the namespaces of the host modules
+	pub fn latest_abi_version(&self) -> Option<u32> {
+		self.modules().filter_map(|(namespace, _)| abi::version(namespace)).max()
+	}
+
+	/// Create a Linker containing the Host Functions of all the host modules
+	pub(crate) fn linker(&self, engine: &Engine) -> Result<Linker<StoreData<S>>, String> {
+		// Two host modules can't define the same import
+		let mut defined = HashMap::new();
+		for (index, (namespace, functions)) in self.modules().enumerate() {
+			for function in functions {
+				if defined.insert((namespace, *function), index).is_some() {
+					return Err(format!("host function {}.{} defined twice", namespace, function))
+				}
+			}
+		}
+
+		let mut linker = Linker::new(engine);
+		for module in &self.modules {
+			// A Host Function not implemented yet, e.g. a `todo!()`, fails the executions
+			// instead of the whole executor
+			let registered = panic::catch_unwind(AssertUnwindSafe(|| module.register(&mut linker)))
+				.map_err(|panic| {
+					format!(
+						"impossible register the host functions of {}: {}",
+						module.namespace(),
+						panic_message(panic.as_ref())
+					)
+				})?;
+			registered.map_err(|err| err.to_string())?;
+		}
+		self.add_compatibility(&mut linker).map_err(|err| err.to_string())?;
+		Ok(linker)
+	}
+
+	/// Names of the Host Functions defined in a namespace by the host modules
+	fn functions_of(&self, namespace: &str) -> Vec<&'static str> {
+		self.modules()
+			.filter(|(module_namespace, _)| *module_namespace == namespace)
+			.flat_map(|(_, functions)| functions.iter().copied())
+			.collect()
+	}
+
+	// Define in every ABI version the Host Functions of the previous versions that are not
+	// redefined, and in the legacy namespace the Host Functions of the first version
+	fn add_compatibility(&self, linker: &mut Linker<StoreData<S>>) -> Result<()> {
+		let latest = self.latest_abi_version();
+
+		// Host Functions available in the previous version
+		let mut inherited: Vec<&str> = Vec::new();
+		for version in 1..=latest.unwrap_or_default() {
+			let namespace = abi::namespace(version);
+			let mut functions = self.functions_of(&namespace);
+
+			for function in inherited {
+				if !functions.contains(&function) {
+					linker.alias(&abi::namespace(version - 1), function, &namespace, function)?;
+					functions.push(function);
+				}
+			}
+			inherited = functions;
+		}
+
+		for function in self.functions_of(&abi::namespace(abi::LEGACY_VERSION)) {
+			linker.alias(
+				&abi::namespace(abi::LEGACY_VERSION),
+				function,
+				abi::LEGACY_NAMESPACE,
+				function,
+			)?;
+		}
+		Ok(())
+	}
+
+	/// Data of the Store of a new execution over the SharedState
+	pub(crate) fn store_data(&self, shared_state: S) -> StoreData<S> {
+		let mut host_data = HashMap::new();
+		for module in &self.modules {
+			module.insert_data(&mut host_data);
+		}
+		StoreData {
+			shared_state,
+			host_data,
+			meter: Meter::new(self.costs.clone()),
+			limits: StoreLimits::default(),
+			forwarded_memory: None,
+		}
+	}
+}
+
+// The message of a panic caught with `catch_unwind`
+pub(crate) fn panic_message(panic: &(dyn Any + Send)) -> &str {
+	match (panic.downcast_ref::<&str>(), panic.downcast_ref::<String>()) {
+		(Some(message), _) => message,
+		(_, Some(message)) => message,
+		_ => "panicked",
+	}
+}
+
+/// The messages logged by the wasm code during an execution
+#[derive(Default)]
+pub struct Logs(pub Vec<String>);
+
+/// `log` lets the wasm code report a message to the executor, the message is
+/// passed as a UTF-8 string in the Linear Memory, see `read_vec`
+pub struct Logging;
+
+impl<S: 'static> HostModule<S> for Logging {
+	type Data = Logs;
+
+	fn namespace(&self) -> &'static str {
+		"env_v1"
+	}
+
+	fn functions(&self) -> &'static [&'static str] {
+		&["log"]
+	}
+
+	fn register(&self, linker: &mut Linker<StoreData<S>>) -> Result<()> {
+		linker.func_wrap(
+			"env_v1",
+			"log",
+			|mut caller: Caller<'_, StoreData<S>>, ptr: u32, size: u32| -> Result<()> {
+				charge(&mut caller, ("env_v1", "log"), size as usize)?;
+				let message = read_vec(&mut caller, ptr, size)?;
+				let message = String::from_utf8_lossy(&message).into_owned();
+				caller.data_mut().host_data_mut::<Logs>().0.push(message);
+				Ok(())
+			},
+		)?;
+		Ok(())
+	}
+}
+
+/// The code proposed by the wasm code during an execution, the last one if more than one
+#[derive(Default)]
+pub struct ProposedCode(pub Option<Vec<u8>>);
+
+/// `set_code` lets the wasm code propose new wasm bytes, passed exactly like the message of
+/// `log`, that replace it after the execution. The new code is validated by the Executor
+/// only once `start` returns, see `Execution::upgrade`.
+pub struct SetCode;
+
+impl<S: 'static> HostModule<S> for SetCode {
+	type Data = ProposedCode;
+
+	fn namespace(&self) -> &'static str {
+		"env_v1"
+	}
+
+	fn functions(&self) -> &'static [&'static str] {
+		&["set_code"]
+	}
+
+	fn register(&self, linker: &mut Linker<StoreData<S>>) -> Result<()> {
+		linker.func_wrap(
+			"env_v1",
+			"set_code",
+			|mut caller: Caller<'_, StoreData<S>>, ptr: u32, size: u32| -> Result<()> {
+				charge(&mut caller, ("env_v1", "set_code"), size as usize)?;
+				let wasm_code = read_vec(&mut caller, ptr, size)?;
+				caller.data_mut().host_data_mut::<ProposedCode>().0 = Some(wasm_code);
+				Ok(())
+			},
+		)?;
+		Ok(())
+	}
+}
+
+/// A panic of the wasm code, reported by its panic hook before the trap
+#[derive(Clone, Serialize)]
+pub struct Panic {
+	pub message: String,
+	pub file: String,
+	pub line: u32,
+}
+
+impl fmt::Display for Panic {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "panicked at {}:{}: {}", self.file, self.line, self.message)
+	}
+}
+
+/// The panic reported during an execution, the last one if more than one
+#[derive(Default)]
+pub struct ReportedPanic(pub Option<Panic>);
+
+/// `panic(message_ptr, message_len, file_ptr, file_len, line)` lets the panic hook of the
+/// wasm code report the panic, the message and the file are UTF-8 strings passed like the
+/// message of `log`. Without it a panic is just an `unreachable` trap, see `Execution::panic`.
+pub struct PanicReporting;
+
+impl<S: 'static> HostModule<S> for PanicReporting {
+	type Data = ReportedPanic;
+
+	fn namespace(&self) -> &'static str {
+		"env_v1"
+	}
+
+	fn functions(&self) -> &'static [&'static str] {
+		&["panic"]
+	}
+
+	fn register(&self, linker: &mut Linker<StoreData<S>>) -> Result<()> {
+		linker.func_wrap(
+			"env_v1",
+			"panic",
+			|mut caller: Caller<'_, StoreData<S>>,
+			 message_ptr: u32,
+			 message_len: u32,
+			 file_ptr: u32,
+			 file_len: u32,
+			 line: u32|
+			 -> Result<()> {
+				charge(&mut caller, ("env_v1", "panic"), message_len as usize + file_len as usize)?;
+				let message = read_vec(&mut caller, message_ptr, message_len)?;
+				let file = read_vec(&mut caller, file_ptr, file_len)?;
+				caller.data_mut().host_data_mut::<ReportedPanic>().0 = Some(Panic {
+					message: String::from_utf8_lossy(&message).into_owned(),
+					file: String::from_utf8_lossy(&file).into_owned(),
+					line,
+				});
+				Ok(())
+			},
+		)?;
+		Ok(())
+	}
+}
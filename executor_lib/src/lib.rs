@@ -0,0 +1,109 @@
+//! Utilities shared by the executors of this activity.
+//!
+//! The library contains everything that is not specific to a single executor:
+//! loading the wasm codes, creating the Engine, instantiating and executing the wasm blobs,
+//! the menu and the command line interface. An executor only needs to define its
+//! `SharedState` and the host modules used to access it, then it can call `run`.
+
+pub mod abi;
+mod async_executor;
+mod backtrace;
+pub mod bench;
+pub mod build;
+mod cli;
+pub mod crypto;
+mod environment;
+mod executor;
+mod host;
+mod memory;
+mod metadata;
+mod pipeline;
+mod pool;
+mod profiler;
+mod repl;
+mod report;
+mod server;
+mod wasm_codes;
+mod watcher;
+mod weight;
+
+pub use async_executor::AsyncExecutor;
+pub use backtrace::{Backtrace, Frame};
+pub use cli::{Cli, Command, EnvProvider, Session, EXIT_FAILURE, EXIT_USAGE};
+pub use crypto::Crypto;
+pub use environment::{Environment, Provider, Recording};
+pub use executor::{
+	DefaultSteps, Execution, ExecutionSteps, Executor, Limits, POOL_INSTANCE_COUNT,
+	POOL_MEMORY_PAGES,
+};
+pub use host::{
+	HostModule, Logging, Logs, Panic, PanicReporting, ProposedCode, Registry, ReportedPanic,
+	SetCode, StoreData,
+};
+pub use memory::{read_vec, write_vec, ForwardedMemory};
+pub use metadata::Metadata;
+pub use pool::{Job, ThreadPool};
+pub use profiler::{HostCalls, Profile, Profiler};
+pub use report::{blob_hash, Report, Status};
+pub use server::{loopback_addr, HttpServer, Server, MAX_EXECUTIONS};
+pub use wasm_codes::{load_wasm_code, BlobInfo, WasmCodes};
+pub use watcher::Watcher;
+pub use weight::{charge, HostCost, HostFunction, HostWeight, Weight};
+
+use clap::Parser;
+use serde::Serialize;
+
+/// Directory containing the wasm codes, relative to the crate of the executor
+pub const WASM_CODES_DIR: &str = "wasm_codes";
+
+/// The SharedState between an executor and all the wasm blobs it executes
+pub trait State: Clone + Serialize + 'static {
+	/// Parse a SharedState from the format used by the menu and the command line
+	fn parse(value: &str) -> Result<Self, String>;
+
+	/// Format the SharedState in the same format accepted by `parse`
+	fn show(&self) -> String;
+}
+
+/// Start the executor: the subcommand provided on the command line is executed,
+/// without one the interactive menu is started.
+///
+/// `initial_state` is the SharedState used if the command line does not provide one,
+/// `registry` contains the host modules provided to the wasm blobs. The `Environment` host
+/// module is added to them, with the Provider selected on the command line.
+pub fn run<S: State>(initial_state: S, registry: Registry<S>) {
+	let cli = Cli::parse();
+	let provider = match cli.provider() {
+		Ok(provider) => provider,
+		Err(err) => {
+			eprintln!("Error: {}", err);
+			std::process::exit(EXIT_FAILURE);
+		},
+	};
+	let registry = registry.with(Environment(provider));
+
+	let state = match cli.state.as_deref().map(S::parse).transpose() {
+		Ok(state) => state.unwrap_or(initial_state),
+		Err(err) => {
+			eprintln!("Error: {}", err);
+			std::process::exit(EXIT_USAGE);
+		},
+	};
+
+	match cli.command {
+		Some(command) => {
+			let executor = match Executor::new(WasmCodes::new(WASM_CODES_DIR), registry) {
+				Ok(executor) => executor,
+				Err(err) => {
+					eprintln!("Error: {}", err);
+					std::process::exit(EXIT_FAILURE);
+				},
+			};
+			std::process::exit(Session::new(executor, state, cli.json).run(command))
+		},
+		None => {
+			let watch = cli.watch || cli.watch_target.is_some();
+			repl::interactive(state, registry, watch, cli.watch_target)
+		},
+	}
+}
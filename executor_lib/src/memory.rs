@@ -0,0 +1,87 @@
+//! Helpers to move vectors between the executor and the Wasm Linear Memory,
+//! to be used in the Host Functions.
+//!
+//! A Host Function can not accept a normal Vec, only numbers,
+//! so a Vec is passed as a pointer (casted to u32) to its beginning in the Linear Memory
+//! and its size.
+
+use wasmtime::{Caller, Extern, Memory, Trap};
+
+/// Data of a Store whose Host Functions can be called by another Host Function with
+/// `Func::call`, as the profiler does to time them. Such a Host Function can't see the
+/// exports of the wasm code, so its Linear Memory is forwarded in the data of the Store.
+pub trait ForwardedMemory {
+	/// The Linear Memory of the wasm code calling the outer Host Function, if any
+	fn forwarded_memory(&self) -> Option<Memory> {
+		None
+	}
+}
+
+/// The function has three input arguments:
+/// + the caller, that give us access to lot of wasm's things, such as the linear memory and other
+///   exports
+/// + the pointer to the beginning of the Vec in the wasm's Linear Memory
+/// + the size of the Vec
+pub fn read_vec<T: ForwardedMemory>(
+	caller: &mut Caller<'_, T>,
+	ptr: u32,
+	size: u32,
+) -> Result<Vec<u8>, Trap> {
+	// First we need to extract the memory, which is defined
+	// as and Export in Wasm
+	let mem = memory(caller)?;
+
+	// From the memory object we can extract the Wasm Linear Memory
+	// as a Slice and then
+	// Use the `ptr` and `size` values to get a sub-slice of the wasm-memory
+	let wasm_slice: Option<&[u8]> =
+		mem.data(&caller).get(ptr as usize..).and_then(|arr| arr.get(..size as usize));
+
+	// If the extraction of the slice from  wasm successful
+	// then translate it to a vec
+	match wasm_slice {
+		Some(w) => Ok(w.to_vec()),
+		None => Err(Trap::UnreachableCodeReached),
+	}
+}
+
+// The input arguments are the same as `read_vec` but they are logically different,
+// the `ptr` and `max_size` refers to an area in the Wasm Linear Memory but not for read it,
+// instead it provided to let write `vec` in it.
+//
+// The return value is the size of the just written vec
+pub fn write_vec<T: ForwardedMemory>(
+	caller: &mut Caller<'_, T>,
+	ptr: u32,
+	max_size: u32,
+	vec: &[u8],
+) -> Result<u32, Trap> {
+	// let's get access to wasm Linear Memory
+	let mem = memory(caller)?;
+
+	// Make sure that the max size of the Vec provided by the
+	// wasm code is enough to contain the new Vec
+	if (max_size as usize) < vec.len() {
+		return Err(Trap::UnreachableCodeReached)
+	}
+
+	// Get the slice we want to use to store the new Vec from the LinearMemory
+	let wasm_buffer = mem
+		.data_mut(caller)
+		.get_mut(ptr as usize..)
+		.and_then(|arr| arr.get_mut(..vec.len()))
+		.ok_or(Trap::UnreachableCodeReached)?;
+
+	// Save the Vec in the just extracted slice
+	wasm_buffer.copy_from_slice(vec);
+
+	Ok(vec.len() as u32)
+}
+
+// The Linear Memory exported by the wasm code, or the one forwarded in the data of the Store
+fn memory<T: ForwardedMemory>(caller: &mut Caller<'_, T>) -> Result<Memory, Trap> {
+	match caller.get_export("memory") {
+		Some(Extern::Memory(mem)) => Ok(mem),
+		_ => caller.data().forwarded_memory().ok_or(Trap::UnreachableCodeReached),
+	}
+}
@@ -0,0 +1,98 @@
+//! Metadata embedded by the wasm codes in the custom section `executor_metadata`,
+//! see the `metadata!` macro of `executor_sdk`.
+//!
+//! The section contains a line `key=value` for each field, they are read before
+//! the instantiation so a wasm code requiring an unsupported ABI version is refused.
+
+use crate::abi;
+use serde::Serialize;
+use std::fmt;
+use wasmparser::{Parser, Payload};
+
+/// Name of the custom section containing the metadata
+pub const SECTION_NAME: &str = "executor_metadata";
+
+#[derive(Clone, Serialize)]
+pub struct Metadata {
+	pub name: String,
+	pub version: String,
+	/// ABI version of the Host Functions required by the wasm code
+	pub abi: u32,
+}
+
+impl Metadata {
+	/// Read the metadata of a wasm code, missing if it does not contain the custom section
+	pub fn parse(wasm_code: &[u8]) -> Result<Option<Self>, String> {
+		// The text format, accepted by wasmtime, can't contain custom sections
+		if !wasm_code.starts_with(b"\0asm") {
+			return Ok(None)
+		}
+
+		let mut metadata = None;
+		for payload in Parser::new(0).parse_all(wasm_code) {
+			match payload.map_err(|err| err.to_string())? {
+				Payload::CustomSection(section) if section.name() == SECTION_NAME => {
+					if metadata.is_some() {
+						return Err("duplicated metadata section".to_string())
+					}
+					metadata = Some(Self::from_section(section.data())?);
+				},
+				_ => (),
+			}
+		}
+		Ok(metadata)
+	}
+
+	fn from_section(data: &[u8]) -> Result<Self, String> {
+		let data = std::str::from_utf8(data).map_err(|_| "metadata are not UTF-8".to_string())?;
+
+		let (mut name, mut version, mut abi) = (None, None, None);
+		for line in data.lines().filter(|line| !line.is_empty()) {
+			match line.split_once('=') {
+				Some(("name", value)) => name = Some(value.to_string()),
+				Some(("version", value)) => version = Some(value.to_string()),
+				Some(("abi", value)) =>
+					abi =
+						Some(value.parse().map_err(|_| format!("invalid ABI version: {}", value))?),
+				// Unknown fields are ignored, they could be added by a newer `metadata!`
+				Some(_) => (),
+				None => return Err(format!("invalid metadata line: {}", line)),
+			}
+		}
+
+		Ok(Metadata {
+			name: name.ok_or("missing metadata field: name")?,
+			version: version.ok_or("missing metadata field: version")?,
+			abi: abi.ok_or("missing metadata field: abi")?,
+		})
+	}
+
+	/// Check that the required ABI version is supported by the executor, whose latest
+	/// version is `supported`, and that the wasm code imports only Host Functions of
+	/// the required version or of an older one
+	pub fn check_abi(&self, imported: Option<u32>, supported: Option<u32>) -> Result<(), String> {
+		if self.abi == 0 || Some(self.abi) > supported {
+			return Err(format!(
+				"incompatible ABI: {} requires v{}, the executor supports up to {}",
+				self.name,
+				self.abi,
+				abi::show(supported)
+			))
+		}
+		if imported > Some(self.abi) {
+			return Err(format!(
+				"incompatible ABI: {} requires v{} but it imports Host Functions of {}",
+				self.name,
+				self.abi,
+				abi::show(imported)
+			))
+		}
+		Ok(())
+	}
+}
+
+impl fmt::Display for Metadata {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{} {}, ABI v{}", self.name, self.version, self.abi)
+	}
+}
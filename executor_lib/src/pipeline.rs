@@ -0,0 +1,124 @@
+//! Execution of a pipeline of wasm blobs over the SharedState.
+//!
+//! The SharedState returned by each stage is the input of the next one,
+//! exactly like executing the blobs one after the other from the menu.
+
+/// Result of a single stage of the pipeline
+pub enum Outcome {
+	/// Contains the new SharedState, formatted to be printed
+	Succeeded(String),
+	Failed(String),
+	/// Not executed because a previous stage failed
+	Skipped,
+}
+
+pub struct Stage {
+	pub name: String,
+	pub outcome: Outcome,
+}
+
+/// Parse the stages of the pipeline from the user input.
+///
+/// The input is a list of wasm blob names separated by commas,
+/// or `@` followed by the path of a manifest file.
+pub fn parse_stages(input: &str) -> Result<Vec<String>, String> {
+	let stages: Vec<String> = match input.strip_prefix('@') {
+		Some(path) => read_manifest(path)?,
+		None => input
+			.split(',')
+			.map(str::trim)
+			.filter(|name| !name.is_empty())
+			.map(String::from)
+			.collect(),
+	};
+
+	match stages.is_empty() {
+		true => Err("empty pipeline".to_string()),
+		false => Ok(stages),
+	}
+}
+
+/// A manifest contains a wasm blob name per line,
+/// empty lines and lines starting with `#` are ignored
+fn read_manifest(path: &str) -> Result<Vec<String>, String> {
+	let manifest = std::fs::read_to_string(path).map_err(|_| "manifest not found")?;
+
+	Ok(manifest
+		.lines()
+		.map(str::trim)
+		.filter(|line| !line.is_empty() && !line.starts_with('#'))
+		.map(String::from)
+		.collect())
+}
+
+/// Execute the stages in order threading the SharedState through them.
+///
+/// `execute` runs a single wasm blob and `show` formats the SharedState returned by
+/// each stage for the report. When a stage fails its input is passed to the next stage,
+/// unless `keep_going` is false, in that case all the remaining stages are skipped.
+///
+/// The returned SharedState is the output of the last succeeded stage.
+pub fn run_pipeline<S: Clone>(
+	stages: &[String],
+	shared_state: S,
+	keep_going: bool,
+	mut execute: impl FnMut(&str, S) -> Result<S, String>,
+	show: impl Fn(&S) -> String,
+) -> (S, Vec<Stage>) {
+	let mut state = shared_state;
+	let mut report = Vec::with_capacity(stages.len());
+	let mut failed = false;
+
+	for name in stages {
+		let outcome = if failed && !keep_going {
+			Outcome::Skipped
+		} else {
+			match execute(name, state.clone()) {
+				Ok(new_state) => {
+					state = new_state;
+					Outcome::Succeeded(show(&state))
+				},
+				Err(err) => {
+					failed = true;
+					Outcome::Failed(err)
+				},
+			}
+		};
+
+		report.push(Stage { name: name.clone(), outcome });
+	}
+
+	(state, report)
+}
+
+/// Print the SharedState returned by each stage and a summary table of the executed stages
+pub fn print_report(report: &[Stage]) {
+	for (index, stage) in report.iter().enumerate() {
+		match &stage.outcome {
+			Outcome::Succeeded(state) =>
+				println!("Stage {} ({}): New SharedState value: {}", index + 1, stage.name, state),
+			Outcome::Failed(err) =>
+				println!("Stage {} ({}): Error: {}", index + 1, stage.name, err),
+			Outcome::Skipped => (),
+		}
+	}
+	println!();
+
+	let width = report.iter().map(|stage| stage.name.len()).fold("Wasm code".len(), usize::max);
+
+	println!("{:<5} | {:<width$} | Result", "Stage", "Wasm code");
+	for (index, stage) in report.iter().enumerate() {
+		let result = match &stage.outcome {
+			Outcome::Succeeded(_) => "ok".to_string(),
+			Outcome::Failed(err) => format!("failed: {}", err),
+			Outcome::Skipped => "skipped".to_string(),
+		};
+		println!("{:<5} | {:<width$} | {}", index + 1, stage.name, result);
+	}
+
+	let succeeded = report
+		.iter()
+		.filter(|stage| matches!(stage.outcome, Outcome::Succeeded(_)))
+		.count();
+	println!("{}/{} stages succeeded", succeeded, report.len());
+}
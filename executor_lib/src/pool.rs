@@ -0,0 +1,180 @@
+//! Concurrent execution of independent wasm blobs.
+//!
+//! The Executor runs one blob at a time. The ThreadPool runs many jobs, each one a blob
+//! and the SharedState it is executed over, on worker threads sharing the same Engine
+//! and Linker. Every blob is compiled and linked only once, by the first worker that
+//! needs it, then all the workers instantiate it in their own Stores. A blob that can't be
+//! loaded is not cached, the next job executing it tries again, e.g. after it is fixed.
+//!
+//! The jobs are independent: the SharedState returned by a job is not seen by the others,
+//! and a job panicking, e.g. in a Host Function, fails without stopping the others.
+
+use crate::{
+	executor::{Execution, Executor, Limits, LoadedBlob},
+	host::{panic_message, Registry},
+	wasm_codes::WasmCodes,
+	State,
+};
+use std::{
+	collections::HashMap,
+	num::NonZeroUsize,
+	panic::{self, AssertUnwindSafe},
+	sync::{Arc, Mutex},
+	thread,
+	time::Instant,
+};
+
+/// A wasm blob to execute over a SharedState, within its limits
+pub struct Job<S> {
+	pub blob: String,
+	pub shared_state: S,
+	pub limits: Limits,
+}
+
+impl<S> Job<S> {
+	/// Job with the default limits, the same of the Executor
+	pub fn new(blob: impl Into<String>, shared_state: S) -> Self {
+		Job { blob: blob.into(), shared_state, limits: Limits::default() }
+	}
+
+	pub fn with_limits(mut self, limits: Limits) -> Self {
+		self.limits = limits;
+		self
+	}
+}
+
+// A blob compiled by the first worker that needs it, the others wait for it
+type CachedBlob<S> = Arc<Mutex<Option<LoadedBlob<S>>>>;
+
+/// Executes many jobs in parallel on a pool of worker threads
+pub struct ThreadPool<S: 'static> {
+	executor: Executor<S>,
+	workers: usize,
+	blobs: Mutex<HashMap<String, CachedBlob<S>>>,
+}
+
+impl<S: State + Send> ThreadPool<S> {
+	/// Pool of `workers` threads, `None` uses one for every available CPU
+	pub fn new(
+		wasm_codes: WasmCodes,
+		registry: Registry<S>,
+		workers: Option<NonZeroUsize>,
+	) -> Result<Self, String> {
+		let workers = workers
+			.or_else(|| thread::available_parallelism().ok())
+			.map_or(1, NonZeroUsize::get);
+		Ok(ThreadPool {
+			executor: Executor::new(wasm_codes, registry)?,
+			workers,
+			blobs: Mutex::new(HashMap::new()),
+		})
+	}
+
+	pub fn workers(&self) -> usize {
+		self.workers
+	}
+
+	/// Forget a loaded blob, the next job executing it will load it again
+	pub fn unload(&self, name: &str) {
+		self.blobs.lock().expect("the cache is never poisoned").remove(name);
+	}
+
+	/// Execute all the jobs, the executions are returned in the same order of the jobs.
+	///
+	/// The code proposed by a job with `set_code` is validated and loaded as `name@hash`,
+	/// see `Execution::upgrade`, so it can be executed by the jobs of a later call.
+	pub fn run(&self, jobs: Vec<Job<S>>) -> Vec<Execution<S>> {
+		let count = jobs.len();
+		let jobs = Mutex::new(jobs.into_iter().enumerate());
+		// The workers take the next job as soon as they finish one
+		let next_job = || jobs.lock().expect("the jobs are never poisoned").next();
+
+		let mut executions: Vec<_> = thread::scope(|scope| {
+			let workers: Vec<_> = (0..self.workers.min(count))
+				.map(|_| {
+					scope.spawn(|| {
+						let mut executions = Vec::new();
+						while let Some((index, job)) = next_job() {
+							executions.push((index, self.execute_caught(job)));
+						}
+						executions
+					})
+				})
+				.collect();
+			workers
+				.into_iter()
+				.flat_map(|worker| worker.join().expect("the workers never panic"))
+				.collect()
+		});
+
+		executions.sort_by_key(|(index, _)| *index);
+		executions.into_iter().map(|(_, execution)| execution).collect()
+	}
+
+	// Execute the job, a panic fails its execution instead of the whole worker
+	fn execute_caught(&self, job: Job<S>) -> Execution<S> {
+		let now = Instant::now();
+		panic::catch_unwind(AssertUnwindSafe(|| self.execute(job))).unwrap_or_else(|panic| {
+			let err = format!("the execution panicked: {}", panic_message(panic.as_ref()));
+			Execution::failed(err, now.elapsed())
+		})
+	}
+
+	fn execute(&self, job: Job<S>) -> Execution<S> {
+		let now = Instant::now();
+
+		let blob = match self.load(&job.blob) {
+			Ok(blob) => blob,
+			Err(err) => return Execution::failed(err, now.elapsed()),
+		};
+
+		let steps = self.executor.registry().steps();
+		let (mut execution, proposed_code) = self.executor.execute_blob(
+			&blob,
+			job.shared_state,
+			&job.limits,
+			|_, instance_pre, store| steps.call_start(instance_pre, store),
+		);
+
+		// Same validation of the Executor, after a successful execution
+		if let (Ok(_), Some(wasm_code)) = (&execution.result, proposed_code) {
+			let upgrade = self.executor.compile(wasm_code.clone()).and_then(|new_blob| {
+				let name = self.executor.add_upgrade(&job.blob, &wasm_code, &new_blob.hash)?;
+				Ok((name, new_blob))
+			});
+			match upgrade {
+				Ok((name, new_blob)) => {
+					let cached = Arc::new(Mutex::new(Some(new_blob)));
+					self.blobs
+						.lock()
+						.expect("the cache is never poisoned")
+						.insert(name.clone(), cached);
+					execution.upgrade = Some(name);
+				},
+				Err(err) => execution.result = Err(format!("invalid code upgrade: {}", err)),
+			}
+		}
+
+		execution.elapsed = now.elapsed();
+		execution
+	}
+
+	// The blob from the cache, loaded only if it is not there: a failed load is not cached
+	fn load(&self, name: &str) -> Result<LoadedBlob<S>, String> {
+		let cached = self.cached(name);
+		// The other workers needing the blob wait until it is loaded
+		let mut cached = cached.lock().expect("the cache is never poisoned");
+		if let Some(blob) = &*cached {
+			return Ok(blob.clone())
+		}
+		let blob = self.executor.load_blob(name)?;
+		*cached = Some(blob.clone());
+		Ok(blob)
+	}
+
+	// The cache entry of a blob, created empty if it is not loaded yet
+	fn cached(&self, name: &str) -> CachedBlob<S> {
+		let mut blobs = self.blobs.lock().expect("the cache is never poisoned");
+		blobs.entry(name.to_string()).or_default().clone()
+	}
+}
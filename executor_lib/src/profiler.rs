@@ -0,0 +1,310 @@
+//! Profiling of the executions, to tune the wasm codes.
+//!
+//! During the execution of `start` the stack of the wasm code is sampled at every epoch,
+//! the epochs are incremented by a ticker thread at a fixed interval. The samples are
+//! collected both by the wasmtime's `GuestProfiler`, which writes a profile for the
+//! Firefox Profiler, and as folded stacks, the input of the flamegraph tools.
+//!
+//! Every Host Function is timed too: the imports of the wasm code are linked to wrappers
+//! that measure the calls of the real Host Functions.
+
+use crate::{
+	backtrace::Frame,
+	executor::{backtrace_config, Execution, Executor},
+	host::{Registry, StoreData},
+	wasm_codes::WasmCodes,
+	State,
+};
+use std::{
+	collections::{BTreeMap, HashMap},
+	fmt, fs,
+	path::Path,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc, Mutex,
+	},
+	thread,
+	time::{Duration, Instant},
+};
+use wasmtime::*;
+
+// Number of guest functions shown in the summary
+const SUMMARY_TOP_FUNCTIONS: usize = 10;
+
+/// The calls of a Host Function
+#[derive(Default)]
+pub struct HostCalls {
+	pub count: u64,
+	pub total: Duration,
+}
+
+/// What has been observed profiling an execution
+pub struct Profile {
+	pub blob: String,
+	pub interval: Duration,
+	/// Number of samples of every stack, the functions are listed from the entry point
+	pub stacks: HashMap<Vec<String>, u64>,
+	/// Calls of every Host Function, named `namespace.function`
+	pub host_calls: BTreeMap<String, HostCalls>,
+	/// Time of the whole execution, loading the blob included
+	pub elapsed: Duration,
+	/// Time of the call of `start`, in which the Host Functions are called
+	pub start: Duration,
+	guest_profiler: GuestProfiler,
+}
+
+// Collects the samples from the epoch callback of the Store
+struct Sampler {
+	guest_profiler: GuestProfiler,
+	stacks: HashMap<Vec<String>, u64>,
+}
+
+impl Sampler {
+	fn sample<T>(&mut self, store: &StoreContextMut<T>) {
+		self.guest_profiler.sample(store);
+
+		// wasmtime lists the frames starting from the innermost
+		let backtrace = WasmBacktrace::force_capture(store);
+		let stack: Vec<_> = backtrace
+			.frames()
+			.iter()
+			.rev()
+			.map(|frame| Frame::new(frame).function)
+			.collect();
+		if !stack.is_empty() {
+			*self.stacks.entry(stack).or_default() += 1;
+		}
+	}
+}
+
+/// Executor profiling every execution.
+///
+/// Its Engine interrupts the wasm code at every epoch, so it can't be used
+/// to execute the wasm codes without profiling them.
+pub struct Profiler<S: 'static> {
+	executor: Executor<S>,
+	interval: Duration,
+}
+
+impl<S: State> Profiler<S> {
+	/// `interval` is the time between two samples
+	pub fn new(
+		wasm_codes: WasmCodes,
+		registry: Registry<S>,
+		interval: Duration,
+	) -> Result<Self, String> {
+		let mut config = backtrace_config();
+		config.epoch_interruption(true);
+		Ok(Profiler { executor: Executor::with_engine(config, wasm_codes, registry)?, interval })
+	}
+
+	/// Execute a wasm blob over the SharedState profiling it,
+	/// the blob is loaded again so a changed wasm code is profiled
+	pub fn profile(&mut self, name: &str, shared_state: S) -> (Execution<S>, Option<Profile>) {
+		self.executor.unload(name);
+
+		let engine = self.executor.engine().clone();
+		let interval = self.interval;
+		let host_calls = Arc::new(Mutex::new(BTreeMap::new()));
+		let mut sampler = None;
+		let mut start = Duration::ZERO;
+
+		let stop = AtomicBool::new(false);
+		let execution = thread::scope(|scope| {
+			scope.spawn(|| {
+				while !stop.load(Ordering::Relaxed) {
+					thread::sleep(interval);
+					engine.increment_epoch();
+				}
+			});
+
+			let execution =
+				self.executor.run_with(name, shared_state, |linker, instance_pre, store| {
+					let module = instance_pre.module();
+					let guest_profiler = GuestProfiler::new(
+						name,
+						interval,
+						vec![(name.to_string(), module.clone())],
+					);
+					let sampler = sampler.insert(Arc::new(Mutex::new(Sampler {
+						guest_profiler,
+						stacks: HashMap::new(),
+					})));
+					call_start_profiled(
+						linker,
+						module,
+						store,
+						sampler.clone(),
+						host_calls.clone(),
+						&mut start,
+					)
+				});
+			stop.store(true, Ordering::Relaxed);
+			execution
+		});
+
+		// The Store, holding the other references, has been dropped with the execution
+		let profile = sampler.map(|sampler| {
+			let sampler = Arc::try_unwrap(sampler)
+				.ok()
+				.expect("the Store is dropped")
+				.into_inner()
+				.expect("the sampler never panics");
+			let host_calls = Arc::try_unwrap(host_calls)
+				.ok()
+				.expect("the Store is dropped")
+				.into_inner()
+				.expect("the timers never panic");
+			Profile {
+				blob: name.to_string(),
+				interval,
+				stacks: sampler.stacks,
+				host_calls,
+				elapsed: execution.elapsed,
+				start,
+				guest_profiler: sampler.guest_profiler,
+			}
+		});
+		(execution, profile)
+	}
+}
+
+// Instantiate the wasm code linking its imports to timed wrappers of the Host Functions
+// and call `start`, sampling the stack at every epoch. The time of the call is `elapsed`.
+fn call_start_profiled<S>(
+	linker: &Linker<StoreData<S>>,
+	module: &Module,
+	store: &mut Store<StoreData<S>>,
+	sampler: Arc<Mutex<Sampler>>,
+	host_calls: Arc<Mutex<BTreeMap<String, HostCalls>>>,
+	elapsed: &mut Duration,
+) -> Result<()> {
+	let mut timed_linker = Linker::new(store.engine());
+	for import in module.imports() {
+		let Some(Extern::Func(host_function)) =
+			linker.get(&mut *store, import.module(), import.name())
+		else {
+			continue
+		};
+
+		let name = format!("{}.{}", import.module(), import.name());
+		let host_calls = host_calls.clone();
+		let ty = host_function.ty(&*store);
+		let timed = Func::new(&mut *store, ty, move |mut caller, params, results| {
+			// The Host Function is not called by the wasm code, so it can't see its memory
+			let memory = caller.get_export("memory").and_then(Extern::into_memory);
+			let previous = std::mem::replace(&mut caller.data_mut().forwarded_memory, memory);
+			let now = Instant::now();
+			let result = host_function.call(&mut caller, params, results);
+			let elapsed = now.elapsed();
+			caller.data_mut().forwarded_memory = previous;
+
+			let mut host_calls = host_calls.lock().expect("the timers never panic");
+			let calls = host_calls.entry(name.clone()).or_default();
+			calls.count += 1;
+			calls.total += elapsed;
+			result
+		});
+		timed_linker.define(&*store, import.module(), import.name(), timed)?;
+	}
+
+	let instance = timed_linker.instantiate(&mut *store, module)?;
+	let start = instance.get_typed_func::<(), ()>(&mut *store, "start")?;
+
+	store.set_epoch_deadline(1);
+	store.epoch_deadline_callback(move |store| {
+		sampler.lock().expect("the sampler never panics").sample(&store);
+		Ok(UpdateDeadline::Continue(1))
+	});
+
+	let now = Instant::now();
+	let result = start.call(store, ());
+	*elapsed = now.elapsed();
+	result
+}
+
+impl Profile {
+	/// The stacks in the folded format, a line for every stack with the functions separated
+	/// by `;` followed by the number of samples, used by `flamegraph.pl` and `inferno`
+	pub fn folded(&self) -> String {
+		let mut lines: Vec<_> = self
+			.stacks
+			.iter()
+			.map(|(stack, samples)| format!("{} {}", stack.join(";"), samples))
+			.collect();
+		lines.sort();
+		lines.into_iter().map(|line| line + "\n").collect()
+	}
+
+	/// Write in `dir` the folded stacks, `<blob>.folded`, and the profile for the
+	/// Firefox Profiler, `<blob>.profile.json`. The paths of the files are returned.
+	pub fn write(self, dir: impl AsRef<Path>) -> Result<Vec<String>, String> {
+		let dir = dir.as_ref();
+		fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+
+		let folded = dir.join(format!("{}.folded", self.blob));
+		fs::write(&folded, self.folded()).map_err(|err| err.to_string())?;
+
+		let profile = dir.join(format!("{}.profile.json", self.blob));
+		let file = fs::File::create(&profile).map_err(|err| err.to_string())?;
+		self.guest_profiler.finish(file).map_err(|err| err.to_string())?;
+
+		Ok(vec![folded.display().to_string(), profile.display().to_string()])
+	}
+
+	// Number of samples in which every function is the innermost one
+	fn self_samples(&self) -> Vec<(&str, u64)> {
+		let mut functions = HashMap::<&str, u64>::new();
+		for (stack, samples) in &self.stacks {
+			if let Some(function) = stack.last() {
+				*functions.entry(function).or_default() += samples;
+			}
+		}
+		let mut functions: Vec<_> = functions.into_iter().collect();
+		functions.sort_by(|(name_a, a), (name_b, b)| b.cmp(a).then(name_a.cmp(name_b)));
+		functions
+	}
+}
+
+/// Summary of the profile: the guest functions with the most samples
+/// and the share of the time of `start` spent in every Host Function
+impl fmt::Display for Profile {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let total_samples: u64 = self.stacks.values().sum();
+		let percent = |part: f64, total: f64| if total > 0.0 { 100.0 * part / total } else { 0.0 };
+
+		writeln!(
+			f,
+			"Profile of {}: {:.3}ms, start {:.3}ms, {} samples every {}us",
+			self.blob,
+			self.elapsed.as_secs_f64() * 1000.0,
+			self.start.as_secs_f64() * 1000.0,
+			total_samples,
+			self.interval.as_micros()
+		)?;
+
+		writeln!(f, "Top guest functions (self samples):")?;
+		for (function, samples) in self.self_samples().into_iter().take(SUMMARY_TOP_FUNCTIONS) {
+			writeln!(
+				f,
+				"  {:>5.1}%  {:>6}  {}",
+				percent(samples as f64, total_samples as f64),
+				samples,
+				function
+			)?;
+		}
+
+		write!(f, "Host functions (share of the time of start):")?;
+		for (name, calls) in &self.host_calls {
+			write!(
+				f,
+				"\n  {:>5.1}%  {:>6} calls  {:>10.3}ms  {}",
+				percent(calls.total.as_secs_f64(), self.start.as_secs_f64()),
+				calls.count,
+				calls.total.as_secs_f64() * 1000.0,
+				name
+			)?;
+		}
+		Ok(())
+	}
+}
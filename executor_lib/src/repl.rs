@@ -0,0 +1,192 @@
+//! Interactive menu of the executors
+
+use crate::{
+	abi, executor::Executor, host::Registry, pipeline, wasm_codes::WasmCodes, watcher::Watcher,
+	weight::Weight, State, WASM_CODES_DIR,
+};
+use std::{io::Read, path::PathBuf};
+
+macro_rules! skip_fail {
+	($res:expr) => {
+		match $res {
+			Ok(val) => val,
+			Err(err) => {
+				println!("Error: {}", err);
+				continue
+			},
+		}
+	};
+}
+
+/// The menu, with `watch` the wasm codes changed on disk and the ones built in `target`
+/// are reloaded, see `watcher`
+pub fn interactive<S: State>(
+	mut state: S,
+	registry: Registry<S>,
+	watch: bool,
+	target: Option<PathBuf>,
+) {
+	let mut prev_executed_wasm_blob = String::new();
+	let wasm_codes = WasmCodes::new(WASM_CODES_DIR);
+	let mut executor = Executor::new(wasm_codes.clone(), registry.clone())
+		.expect("impossible create the executor");
+	let watcher = watch.then(|| Watcher::start(&executor, target));
+	// Created the first time a benchmark is requested, it does not see the reloads of the
+	// executor, so the benchmarked blob is refreshed every time, see `Executor::throughput`
+	let mut pooled_executor: Option<Executor<S>> = None;
+
+	loop {
+		println!();
+		println!("Options:");
+		println!("1 -> Change SharedState");
+		println!("2 -> Execute wasm code");
+		println!("3 -> Execute previous wasm code ({})", prev_executed_wasm_blob);
+		println!("4 -> List wasm codes");
+		println!("5 -> Execute a pipeline of wasm codes");
+		println!(
+			"6 -> Benchmark previous wasm code with the pooling allocator ({})",
+			prev_executed_wasm_blob
+		);
+		println!("Current SharedState value: {}", state.show());
+		println!();
+
+		let option = skip_fail!(get_input());
+		// The wasm codes could be changed while waiting for the option
+		if let Some(watcher) = &watcher {
+			watcher.apply(&mut executor);
+		}
+		match option {
+			1 => {
+				println!("Insert new State: ");
+				let input: String = skip_fail!(get_input());
+				state = skip_fail!(S::parse(&input));
+			},
+			2 => {
+				println!("Insert wasm code name: ");
+				let wasm_code_name: String = skip_fail!(get_input());
+
+				// The wasm code could be changed since the last execution
+				executor.refresh(&wasm_code_name);
+				let abi_version = skip_fail!(executor.abi_version(&wasm_code_name));
+				println!("ABI version: {}", abi::show(abi_version));
+				let execution = executor.run(&wasm_code_name, state.clone());
+				state = skip_fail!(execution.result);
+				println!("New SharedState value: {}", state.show());
+				weight(execution.weight);
+				prev_executed_wasm_blob = upgraded(execution.upgrade).unwrap_or(wasm_code_name);
+			},
+			3 =>
+				if !prev_executed_wasm_blob.is_empty() {
+					let execution = executor.run(&prev_executed_wasm_blob, state.clone());
+					state = skip_fail!(execution.result);
+					println!("New SharedState value: {}", state.show());
+					weight(execution.weight);
+					if let Some(upgrade) = upgraded(execution.upgrade) {
+						prev_executed_wasm_blob = upgrade;
+					}
+				} else {
+					println!("No previous wasm blob");
+				},
+			4 => {
+				let names = skip_fail!(wasm_codes.list());
+
+				println!("Wasm codes:");
+				for name in names {
+					match executor.metadata(&name) {
+						Ok(Some(metadata)) => println!("{} ({})", name, metadata),
+						Ok(None) => println!("{} (no metadata)", name),
+						Err(err) => println!("{} (invalid metadata: {})", name, err),
+					}
+				}
+			},
+			5 => {
+				println!("Insert wasm code names separated by commas, or @ followed by a manifest file: ");
+				let input: String = skip_fail!(get_input());
+				let stages = skip_fail!(pipeline::parse_stages(&input));
+
+				println!("Keep going after a failed stage? (y/n): ");
+				let keep_going = skip_fail!(get_yes_no());
+
+				let (new_state, report) = pipeline::run_pipeline(
+					&stages,
+					state.clone(),
+					keep_going,
+					|name, state| executor.execute(name, state),
+					S::show,
+				);
+				state = new_state;
+
+				pipeline::print_report(&report);
+			},
+			6 =>
+				if !prev_executed_wasm_blob.is_empty() {
+					println!("Insert number of executions: ");
+					let executions: u32 = skip_fail!(get_input());
+
+					if pooled_executor.is_none() {
+						pooled_executor = Some(skip_fail!(Executor::pooled(
+							wasm_codes.clone(),
+							registry.clone()
+						)));
+					}
+					let pooled_executor = pooled_executor.as_mut().expect("just initialized");
+
+					// Every execution starts from the current SharedState,
+					// the benchmark does not modify it
+					let elapsed = skip_fail!(pooled_executor.throughput(
+						&prev_executed_wasm_blob,
+						&state,
+						executions
+					));
+
+					println!(
+						"{} executions in {:.3}s ({:.0} executions/s)",
+						executions,
+						elapsed.as_secs_f64(),
+						executions as f64 / elapsed.as_secs_f64()
+					);
+				} else {
+					println!("No previous wasm blob");
+				},
+			_ => println!("Not valid Option"),
+		}
+
+		println!();
+		println!("Press Enter to continue");
+		// Only the first byte is consumed, as before the subcommands were added
+		let _read = std::io::stdin().read(&mut [0]).unwrap();
+	}
+}
+
+// Notify that the executed wasm code has been replaced using `set_code`
+fn upgraded(upgrade: Option<String>) -> Option<String> {
+	if let Some(upgrade) = &upgrade {
+		println!("The wasm code has been upgraded, the previous wasm code is now {}", upgrade);
+	}
+	upgrade
+}
+
+// Show the weight of a successful execution
+fn weight(weight: Option<Weight>) {
+	if let Some(weight) = weight {
+		println!("Weight: {}", weight);
+	}
+}
+
+fn get_input<T: std::str::FromStr>() -> Result<T, &'static str> {
+	let mut input_line = String::new();
+
+	std::io::stdin().read_line(&mut input_line).expect("Failed to read line");
+
+	println!();
+
+	input_line.trim().parse::<T>().map_err(|_| "Impossible Parse Input")
+}
+
+fn get_yes_no() -> Result<bool, &'static str> {
+	match get_input::<String>()?.as_str() {
+		"y" | "Y" => Ok(true),
+		"n" | "N" => Ok(false),
+		_ => Err("Impossible Parse Input"),
+	}
+}
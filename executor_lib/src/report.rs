@@ -0,0 +1,125 @@
+//! Machine-readable report of an execution, printed as a JSON document on a single line.
+
+use crate::{
+	backtrace::Backtrace, environment::Recording, executor::Execution, host::Panic, weight::Weight,
+	State,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+/// Hex encoded SHA-256 of a wasm code
+pub fn blob_hash(wasm_code: &[u8]) -> String {
+	Sha256::digest(wasm_code).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+	Success,
+	/// The wasm code trapped during the execution
+	Trap,
+	/// The execution failed for any other reason, e.g. the wasm code was not found
+	Error,
+}
+
+/// The input and the output are the SharedState for the executors,
+/// or the arguments and the result of the called function for the simple executor.
+#[derive(Serialize)]
+pub struct Report<I, O = I> {
+	pub blob: String,
+	pub blob_hash: Option<String>,
+	/// ABI version used by the wasm code, missing if it imports no Host Functions of a version
+	pub abi_version: Option<u32>,
+	pub input: I,
+	pub output: Option<O>,
+	pub status: Status,
+	/// Name of the wasmtime trap, set only if the status is `trap`
+	pub trap_code: Option<String>,
+	/// Set if the status is not `success`
+	pub error: Option<String>,
+	pub fuel_used: Option<u64>,
+	/// The fuel used split between the wasm instructions and the Host Functions
+	pub weight: Option<Weight>,
+	pub wall_time_us: u128,
+	pub logs: Vec<String>,
+	/// The panic reported by the wasm code, set only if the status is `trap`
+	pub panic: Option<Panic>,
+	/// The wasm frames, from the entry point to the one that failed
+	pub backtrace: Option<Backtrace>,
+	/// Name of the blob that replaced the executed one with `set_code`
+	pub upgrade: Option<String>,
+	/// The time and the random bytes provided to the wasm code, they can be served again
+	/// with `--replay`
+	pub environment: Option<Recording>,
+}
+
+impl<I: Serialize, O: Serialize> Report<I, O> {
+	/// Report of an execution observed only from its result,
+	/// traps are reported as any other error and the fuel is not metered
+	pub fn new(
+		blob: &str,
+		blob_hash: Option<String>,
+		input: I,
+		result: Result<O, String>,
+		elapsed: Duration,
+	) -> Self {
+		let (output, status, error) = match result {
+			Ok(output) => (Some(output), Status::Success, None),
+			Err(err) => (None, Status::Error, Some(err)),
+		};
+
+		Report {
+			blob: blob.to_string(),
+			blob_hash,
+			abi_version: None,
+			input,
+			output,
+			status,
+			trap_code: None,
+			error,
+			fuel_used: None,
+			weight: None,
+			wall_time_us: elapsed.as_micros(),
+			logs: Vec::new(),
+			panic: None,
+			backtrace: None,
+			upgrade: None,
+			environment: None,
+		}
+	}
+
+	pub fn print(&self) {
+		println!("{}", serde_json::to_string(self).expect("a report is always serializable"));
+	}
+}
+
+impl<S: State> Report<S> {
+	/// Report of an execution of the Executor over the SharedState `input`
+	pub fn from_execution(blob: &str, input: &S, execution: Execution<S>) -> Self {
+		let (output, status, error) = match execution.result {
+			Ok(state) => (Some(state), Status::Success, None),
+			Err(err) if execution.trap.is_some() => (None, Status::Trap, Some(err)),
+			Err(err) => (None, Status::Error, Some(err)),
+		};
+
+		Report {
+			blob: blob.to_string(),
+			blob_hash: execution.blob_hash,
+			abi_version: execution.abi_version,
+			input: input.clone(),
+			output,
+			status,
+			trap_code: execution.trap.map(|trap| format!("{:?}", trap)),
+			error,
+			fuel_used: execution.fuel_consumed,
+			weight: execution.weight,
+			wall_time_us: execution.elapsed.as_micros(),
+			logs: execution.logs,
+			panic: execution.panic,
+			backtrace: execution.backtrace,
+			upgrade: execution.upgrade,
+			environment: execution.environment,
+		}
+	}
+}
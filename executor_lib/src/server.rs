@@ -0,0 +1,244 @@
+//! The executor as a local service, exposed with JSON-RPC 2.0 over HTTP.
+//!
+//! Every request is a POST whose body is a JSON-RPC request, the methods are:
+//! + `upload {name, code, tag}`: add the wasm code, encoded in base64, with the name as alias and
+//!   the optional version tag, see `WasmCodes::add`
+//! + `list`: the names of the wasm codes
+//! + `info {name}`: what is recorded about a wasm code, see `BlobInfo`
+//! + `execute {blob, state}`: execute the blob over the SharedState, in the format accepted by
+//!   `State::parse`, and return the id and the report of the execution
+//! + `result {id}`: the report of an execution, see `Report`
+//! + `logs {id}`: the messages logged during an execution
+//!
+//! The requests are handled one at a time by the same Executor, like the commands of a script.
+//! Only the reports of the last `MAX_EXECUTIONS` executions are kept, the older ids are unknown.
+//!
+//! The server has no authentication, it listens only on a loopback address.
+
+use crate::{executor::Executor, host::Registry, report::Report, wasm_codes::WasmCodes, State};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use std::{
+	collections::VecDeque,
+	net::{SocketAddr, ToSocketAddrs},
+};
+use tiny_http::{Header, Method, Response};
+
+/// Number of execution reports kept by the server
+pub const MAX_EXECUTIONS: usize = 256;
+
+// Error codes defined by JSON-RPC 2.0
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+// Error of a method that could not be completed, e.g. a wasm code not found
+const SERVER_ERROR: i64 = -32000;
+
+#[derive(Deserialize)]
+struct Request {
+	jsonrpc: String,
+	method: String,
+	#[serde(default)]
+	params: Value,
+	#[serde(default)]
+	id: Value,
+}
+
+#[derive(Deserialize)]
+struct UploadParams {
+	name: String,
+	code: String,
+	tag: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct NameParams {
+	name: String,
+}
+
+#[derive(Deserialize)]
+struct ExecuteParams {
+	blob: String,
+	state: String,
+}
+
+#[derive(Deserialize)]
+struct ExecutionParams {
+	id: usize,
+}
+
+// Error of a request, reported in the `error` member of the response
+struct Error {
+	code: i64,
+	message: String,
+}
+
+impl Error {
+	fn new(code: i64, message: impl ToString) -> Self {
+		Error { code, message: message.to_string() }
+	}
+}
+
+/// Handles the JSON-RPC requests with an Executor, keeping the reports of the executions
+pub struct Server<S: 'static> {
+	executor: Executor<S>,
+	/// The last executions, the id of an execution is its index plus `first_execution`
+	executions: VecDeque<Report<S>>,
+	first_execution: usize,
+}
+
+impl<S: State> Server<S> {
+	pub fn new(wasm_codes: WasmCodes, registry: Registry<S>) -> Result<Self, String> {
+		Ok(Server {
+			executor: Executor::new(wasm_codes, registry)?,
+			executions: VecDeque::new(),
+			first_execution: 0,
+		})
+	}
+
+	/// Handle a JSON-RPC request and return the response
+	pub fn handle(&mut self, request: &str) -> Value {
+		let request = match serde_json::from_str::<Value>(request) {
+			Ok(request) => request,
+			Err(err) => return error_response(Value::Null, Error::new(PARSE_ERROR, err)),
+		};
+		let request = match serde_json::from_value::<Request>(request) {
+			Ok(request) if request.jsonrpc == "2.0" => request,
+			Ok(request) =>
+				return error_response(
+					request.id,
+					Error::new(INVALID_REQUEST, "jsonrpc must be 2.0"),
+				),
+			Err(err) => return error_response(Value::Null, Error::new(INVALID_REQUEST, err)),
+		};
+
+		match self.call(&request.method, request.params) {
+			Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": request.id }),
+			Err(err) => error_response(request.id, err),
+		}
+	}
+
+	fn call(&mut self, method: &str, params: Value) -> Result<Value, Error> {
+		match method {
+			"upload" => {
+				let UploadParams { name, code, tag } = parse_params(params)?;
+				let wasm_code =
+					BASE64.decode(code).map_err(|err| Error::new(INVALID_PARAMS, err))?;
+				// Refuse a wasm code that can't be executed before adding it
+				self.executor.compile(wasm_code.clone()).map_err(server_error)?;
+				let info = self
+					.executor
+					.wasm_codes()
+					.add(&wasm_code, Some(&name), tag.as_deref())
+					.map_err(server_error)?;
+				// The next execution loads the new wasm code
+				self.executor.unload(&name);
+				Ok(json!({ "name": name, "hash": info.hash }))
+			},
+			"list" => Ok(json!(self.executor.wasm_codes().list().map_err(server_error)?)),
+			"info" => {
+				let NameParams { name } = parse_params(params)?;
+				Ok(json!(self.executor.wasm_codes().info(&name).map_err(server_error)?))
+			},
+			"execute" => {
+				let ExecuteParams { blob, state } = parse_params(params)?;
+				let state = S::parse(&state).map_err(|err| Error::new(INVALID_PARAMS, err))?;
+				// The wasm code could be changed since the last execution
+				self.executor.refresh(&blob);
+				let execution = self.executor.run(&blob, state.clone());
+				let report = Report::from_execution(&blob, &state, execution);
+
+				let id = self.first_execution + self.executions.len();
+				let response = json!({ "id": id, "report": report });
+				if self.executions.len() == MAX_EXECUTIONS {
+					self.executions.pop_front();
+					self.first_execution += 1;
+				}
+				self.executions.push_back(report);
+				Ok(response)
+			},
+			"result" => Ok(json!(self.execution(parse_params(params)?)?)),
+			"logs" => Ok(json!(self.execution(parse_params(params)?)?.logs)),
+			_ => Err(Error::new(METHOD_NOT_FOUND, format!("unknown method: {}", method))),
+		}
+	}
+
+	fn execution(&self, ExecutionParams { id }: ExecutionParams) -> Result<&Report<S>, Error> {
+		id.checked_sub(self.first_execution)
+			.and_then(|index| self.executions.get(index))
+			.ok_or(Error::new(SERVER_ERROR, format!("unknown execution: {}", id)))
+	}
+}
+
+/// The HTTP server of the JSON-RPC requests, it listens only on a loopback address
+pub struct HttpServer<S: 'static> {
+	server: Server<S>,
+	http: tiny_http::Server,
+}
+
+impl<S: State> HttpServer<S> {
+	/// Listen on the address, e.g. `127.0.0.1:8080`, the port 0 chooses a free one.
+	/// An address that is not a loopback one is refused, see `loopback_addr`.
+	pub fn bind(server: Server<S>, addr: &str) -> Result<Self, String> {
+		let http = tiny_http::Server::http(loopback_addr(addr)?).map_err(|err| err.to_string())?;
+		Ok(HttpServer { server, http })
+	}
+
+	pub fn local_addr(&self) -> Option<SocketAddr> {
+		self.http.server_addr().to_ip()
+	}
+
+	/// Handle the requests until the process ends
+	pub fn serve(mut self) {
+		let content_type =
+			Header::from_bytes("Content-Type", "application/json").expect("the header is valid");
+
+		for mut request in self.http.incoming_requests() {
+			if *request.method() != Method::Post {
+				// A failed response can only be ignored, the client is gone
+				let _ = request.respond(Response::empty(405));
+				continue
+			}
+
+			let mut body = String::new();
+			let response = match request.as_reader().read_to_string(&mut body) {
+				Ok(_) => self.server.handle(&body),
+				Err(err) => error_response(Value::Null, Error::new(PARSE_ERROR, err)),
+			};
+			let response =
+				Response::from_string(response.to_string()).with_header(content_type.clone());
+			let _ = request.respond(response);
+		}
+	}
+}
+
+/// Resolve the address, e.g. `127.0.0.1:8080` or `localhost:8080`, and refuse it unless it is
+/// a loopback one: the server must not be reachable from the network
+pub fn loopback_addr(addr: &str) -> Result<SocketAddr, String> {
+	let addrs: Vec<_> = addr
+		.to_socket_addrs()
+		.map_err(|err| format!("invalid address {}: {}", addr, err))?
+		.collect();
+	match addrs.first() {
+		Some(first) if addrs.iter().all(|addr| addr.ip().is_loopback()) => Ok(*first),
+		_ => Err(format!("not a loopback address: {}", addr)),
+	}
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, Error> {
+	serde_json::from_value(params).map_err(|err| Error::new(INVALID_PARAMS, err))
+}
+
+fn server_error(err: impl ToString) -> Error {
+	Error::new(SERVER_ERROR, err)
+}
+
+fn error_response(id: Value, err: Error) -> Value {
+	json!({
+		"jsonrpc": "2.0",
+		"error": { "code": err.code, "message": err.message },
+		"id": id,
+	})
+}
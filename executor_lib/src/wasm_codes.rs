@@ -0,0 +1,311 @@
+//! The wasm codes available to the executors, stored by content hash.
+//!
+//! A wasm code added to the directory is saved as `blobs/<hash>.wasm`, where the hash is its
+//! SHA-256, and `index.json` records its size, imports, exports and when it was added. An
+//! alias is a human name pointing to the last wasm code added with it, the previous ones
+//! stay available with their version tags and their hashes. The same code is stored once,
+//! and a tag always identifies the same code, so nothing is overwritten.
+//!
+//! A wasm code is identified by:
+//! + `alias`: the last wasm code added with the alias
+//! + `alias:tag`: the wasm code added with the alias and the version tag
+//! + its hash, or a prefix of at least 8 hex digits identifying a single wasm code
+//! + the name of a `.wasm` file in the directory, the wasm codes copied there by hand
+
+use crate::report::blob_hash;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::BTreeMap,
+	fs,
+	path::{Path, PathBuf},
+	time::{SystemTime, UNIX_EPOCH},
+};
+use wasmparser::{Parser, Payload};
+
+// Index of the stored wasm codes, in the directory
+const INDEX_FILE: &str = "index.json";
+// Directory of the stored wasm codes, in the directory
+const BLOBS_DIR: &str = "blobs";
+// Minimum number of hex digits of a hash prefix
+const MIN_HASH_PREFIX: usize = 8;
+// Number of hex digits of the hashes in the errors, the notices and the names of the upgrades
+pub(crate) const SHORT_HASH_LEN: usize = 12;
+
+/// Helper function to load a wasm file
+/// If the path provided does not end with `.wasm` it is appended.
+pub fn load_wasm_code(path: impl AsRef<Path>) -> Result<Vec<u8>, &'static str> {
+	std::fs::read(wasm_path(path.as_ref())).map_err(|_| "file not found")
+}
+
+/// What is recorded about a wasm code
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BlobInfo {
+	/// Hex encoded SHA-256, see `blob_hash`
+	pub hash: String,
+	pub size: usize,
+	/// Imported functions, as `namespace.function`
+	pub imports: Vec<String>,
+	pub exports: Vec<String>,
+	/// Seconds since the UNIX epoch, the modification time for a `.wasm` file
+	pub added: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Alias {
+	/// Hash of the last wasm code added with the alias
+	latest: String,
+	/// Hash of the wasm code of every version tag
+	tags: BTreeMap<String, String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Index {
+	/// In the order they were added
+	blobs: Vec<BlobInfo>,
+	aliases: BTreeMap<String, Alias>,
+}
+
+// Where a name identifies a wasm code
+enum Location {
+	Stored(String),
+	File(PathBuf),
+}
+
+/// The directory containing the wasm codes
+#[derive(Clone)]
+pub struct WasmCodes {
+	dir: PathBuf,
+}
+
+impl WasmCodes {
+	pub fn new(dir: impl Into<PathBuf>) -> Self {
+		Self { dir: dir.into() }
+	}
+
+	pub fn dir(&self) -> &Path {
+		&self.dir
+	}
+
+	/// Load the wasm code identified by the name, see the module documentation.
+	/// For a `.wasm` file the extension is optional.
+	pub fn load(&self, name: &str) -> Result<Vec<u8>, &'static str> {
+		match self.locate(name)? {
+			Location::Stored(hash) => fs::read(self.blob_path(&hash)).map_err(|_| "file not found"),
+			Location::File(path) => load_wasm_code(path),
+		}
+	}
+
+	/// Add a wasm code, in the binary format, with an optional alias and version tag.
+	/// A tag of the alias already identifying another wasm code is refused.
+	pub fn add(
+		&self,
+		wasm_code: &[u8],
+		alias: Option<&str>,
+		tag: Option<&str>,
+	) -> Result<BlobInfo, String> {
+		if let Some(alias) = alias {
+			check_alias(alias)?;
+		}
+		let tag = match (alias, tag) {
+			(Some(alias), Some(tag)) => Some((alias, tag)),
+			(None, Some(_)) => return Err("a version tag needs an alias".to_string()),
+			(_, None) => None,
+		};
+		if let Some((_, tag)) = tag {
+			check_alias(tag).map_err(|_| format!("invalid version tag: {}", tag))?;
+		}
+
+		let hash = blob_hash(wasm_code);
+		let mut index = self.index()?;
+		if let Some((alias, tag)) = tag {
+			let tagged = index.aliases.get(alias).and_then(|alias| alias.tags.get(tag));
+			match tagged {
+				Some(tagged) if *tagged != hash =>
+					return Err(format!(
+						"{}:{} already identifies the wasm code {}",
+						alias,
+						tag,
+						&tagged[..SHORT_HASH_LEN]
+					)),
+				_ => (),
+			}
+		}
+
+		let info = match index.blobs.iter().find(|info| info.hash == hash) {
+			Some(info) => info.clone(),
+			None => {
+				let info = blob_info(wasm_code, now())?;
+				fs::create_dir_all(self.dir.join(BLOBS_DIR)).map_err(|err| err.to_string())?;
+				fs::write(self.blob_path(&hash), wasm_code).map_err(|err| err.to_string())?;
+				index.blobs.push(info.clone());
+				info
+			},
+		};
+
+		if let Some(alias) = alias {
+			let entry = index
+				.aliases
+				.entry(alias.to_string())
+				.or_insert_with(|| Alias { latest: hash.clone(), tags: BTreeMap::new() });
+			entry.latest = hash.clone();
+			if let Some((_, tag)) = tag {
+				entry.tags.insert(tag.to_string(), hash);
+			}
+		}
+		self.write_index(&index)?;
+		Ok(info)
+	}
+
+	/// What is recorded about the wasm code identified by the name
+	pub fn info(&self, name: &str) -> Result<BlobInfo, String> {
+		match self.locate(name)? {
+			Location::Stored(hash) => self
+				.index()?
+				.blobs
+				.into_iter()
+				.find(|info| info.hash == hash)
+				.ok_or("missing wasm code in the index".to_string()),
+			Location::File(path) => {
+				let wasm_code = load_wasm_code(&path)?;
+				let added = wasm_path(&path)
+					.metadata()
+					.and_then(|metadata| metadata.modified())
+					.map(seconds)
+					.unwrap_or_default();
+				blob_info(&wasm_code, added)
+			},
+		}
+	}
+
+	/// List the aliases, each followed by its `alias:tag` versions,
+	/// then the names of the `.wasm` files
+	pub fn list(&self) -> Result<Vec<String>, &'static str> {
+		let paths =
+			std::fs::read_dir(&self.dir).map_err(|_| "wasm codes directory does not exist")?;
+
+		let mut names = Vec::new();
+		let index = self.index().map_err(|_| "invalid wasm codes index")?;
+		for (name, alias) in index.aliases {
+			names.extend(alias.tags.into_keys().map(|tag| format!("{}:{}", name, tag)));
+			names.push(name);
+		}
+		// Each alias comes before its versions
+		names.sort_by(|a, b| a.split(':').cmp(b.split(':')));
+
+		let mut files = Vec::new();
+		for path in paths {
+			let path = path.map_err(|_| "impossible list wasm codes names")?.path();
+			if path.is_file() && path.extension().is_some_and(|extension| extension == "wasm") {
+				let name = path.file_name().expect("a file has a name").to_os_string();
+				files.push(name.into_string().map_err(|_| "impossible list wasm codes names")?);
+			}
+		}
+		files.sort();
+		names.extend(files);
+		Ok(names)
+	}
+
+	fn locate(&self, name: &str) -> Result<Location, &'static str> {
+		// A name is never a path, nothing outside the directory can be loaded
+		if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+			return Err("invalid name")
+		}
+		let index = self.index().map_err(|_| "invalid wasm codes index")?;
+
+		if let Some((alias, tag)) = name.split_once(':') {
+			let alias = index.aliases.get(alias).ok_or("unknown alias")?;
+			let hash = alias.tags.get(tag).ok_or("unknown version tag")?;
+			return Ok(Location::Stored(hash.clone()))
+		}
+		if let Some(alias) = index.aliases.get(name) {
+			return Ok(Location::Stored(alias.latest.clone()))
+		}
+		if is_hash_prefix(name) {
+			let mut matching = index.blobs.iter().filter(|info| info.hash.starts_with(name));
+			if let Some(info) = matching.next() {
+				if matching.next().is_some() {
+					return Err("ambiguous hash prefix")
+				}
+				return Ok(Location::Stored(info.hash.clone()))
+			}
+		}
+		Ok(Location::File(self.dir.join(name)))
+	}
+
+	fn blob_path(&self, hash: &str) -> PathBuf {
+		self.dir.join(BLOBS_DIR).join(format!("{}.wasm", hash))
+	}
+
+	// The index, empty if no wasm code has been added yet
+	fn index(&self) -> Result<Index, String> {
+		match fs::read(self.dir.join(INDEX_FILE)) {
+			Ok(index) => serde_json::from_slice(&index)
+				.map_err(|err| format!("invalid wasm codes index: {}", err)),
+			Err(_) => Ok(Index::default()),
+		}
+	}
+
+	// Replace the index, a crash while writing it leaves the previous one
+	fn write_index(&self, index: &Index) -> Result<(), String> {
+		let index = serde_json::to_vec_pretty(index).map_err(|err| err.to_string())?;
+		let tmp = self.dir.join(format!("{}.tmp", INDEX_FILE));
+		fs::write(&tmp, index).map_err(|err| err.to_string())?;
+		fs::rename(tmp, self.dir.join(INDEX_FILE)).map_err(|err| err.to_string())
+	}
+}
+
+// Aliases and tags can't be confused with the other names of the wasm codes
+fn check_alias(alias: &str) -> Result<(), String> {
+	if alias.is_empty() ||
+		alias.starts_with('.') ||
+		alias.ends_with(".wasm") ||
+		alias.contains([':', '/', '\\']) ||
+		is_hash_prefix(alias)
+	{
+		return Err(format!("invalid alias: {}", alias))
+	}
+	Ok(())
+}
+
+fn is_hash_prefix(name: &str) -> bool {
+	(MIN_HASH_PREFIX..=64).contains(&name.len()) &&
+		name.bytes().all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+// If the file does not end with a `.wasm` extension, add it.
+fn wasm_path(path: &Path) -> PathBuf {
+	match path.extension().is_some_and(|extension| extension == "wasm") {
+		true => path.to_path_buf(),
+		false => PathBuf::from(format!("{}.wasm", path.display())),
+	}
+}
+
+fn blob_info(wasm_code: &[u8], added: u64) -> Result<BlobInfo, String> {
+	let (mut imports, mut exports) = (Vec::new(), Vec::new());
+	for payload in Parser::new(0).parse_all(wasm_code) {
+		match payload.map_err(|err| format!("invalid wasm code: {}", err))? {
+			Payload::ImportSection(reader) =>
+				for import in reader {
+					let import = import.map_err(|err| err.to_string())?;
+					imports.push(format!("{}.{}", import.module, import.name));
+				},
+			Payload::ExportSection(reader) =>
+				for export in reader {
+					exports.push(export.map_err(|err| err.to_string())?.name.to_string());
+				},
+			_ => (),
+		}
+	}
+
+	Ok(BlobInfo { hash: blob_hash(wasm_code), size: wasm_code.len(), imports, exports, added })
+}
+
+fn seconds(time: SystemTime) -> u64 {
+	time.duration_since(UNIX_EPOCH)
+		.map(|elapsed| elapsed.as_secs())
+		.unwrap_or_default()
+}
+
+fn now() -> u64 {
+	seconds(SystemTime::now())
+}
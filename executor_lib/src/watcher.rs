@@ -0,0 +1,185 @@
+//! Hot reload of the wasm codes changed while the menu is running.
+//!
+//! A thread polls the `.wasm` files of the wasm codes directory and, optionally, of the
+//! output directory of a guest crate, e.g. `wasm_code/target/wasm32-unknown-unknown/release`.
+//! A changed wasm code is compiled right away, sharing the Engine of the menu, and a notice
+//! is printed with the compile error if any. The built wasm codes of the output directory are
+//! added to the wasm codes with their file name as alias, see `WasmCodes::add`.
+//!
+//! A changed file is compiled from its path: an alias with the same name as the file keeps
+//! identifying its own wasm code.
+//!
+//! The compiled blobs are handed to the menu, which replaces the loaded ones before executing.
+//! Any other executor built on the Executor can watch its wasm codes the same way.
+
+use crate::{
+	executor::{Executor, LoadedBlob},
+	wasm_codes::SHORT_HASH_LEN,
+	State,
+};
+use std::{
+	collections::HashMap,
+	fs,
+	path::{Path, PathBuf},
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		mpsc::{self, Receiver, Sender},
+		Arc,
+	},
+	thread,
+	time::{Duration, SystemTime},
+};
+
+// Time between two checks of the directories
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Modification time and size of every `.wasm` file of a directory
+type Snapshot = HashMap<PathBuf, (SystemTime, u64)>;
+
+// A wasm code changed on disk, with the names identifying it
+enum Update<S: 'static> {
+	Compiled(Vec<String>, LoadedBlob<S>),
+	/// The wasm code can't be compiled, it is loaded again by the next execution
+	/// so the error is reported
+	Failed(Vec<String>),
+}
+
+/// Watches the wasm codes of an Executor until it is dropped
+pub struct Watcher<S: 'static> {
+	updates: Receiver<Update<S>>,
+	stop: Arc<AtomicBool>,
+}
+
+impl<S: State> Watcher<S> {
+	/// Watch the wasm codes of the Executor and the built wasm codes in `target`
+	pub fn start(executor: &Executor<S>, target: Option<PathBuf>) -> Self {
+		let (sender, updates) = mpsc::channel();
+		let stop = Arc::new(AtomicBool::new(false));
+
+		let executor = executor.fork();
+		let stopped = stop.clone();
+		// The files changed once `start` returns are reloaded
+		let wasm_codes_dir = executor.wasm_codes().dir().to_path_buf();
+		let mut wasm_codes = snapshot(&wasm_codes_dir);
+		let mut built = target.as_deref().map(snapshot).unwrap_or_default();
+		thread::spawn(move || {
+			while !stopped.load(Ordering::Relaxed) {
+				thread::sleep(POLL_INTERVAL);
+
+				if let Some(target) = &target {
+					for path in changed(&mut built, snapshot(target)) {
+						add_built(&executor, &path, &sender);
+					}
+				}
+				for path in changed(&mut wasm_codes, snapshot(&wasm_codes_dir)) {
+					reload(&executor, &path, &sender);
+				}
+			}
+		});
+
+		Watcher { updates, stop }
+	}
+
+	/// Replace the loaded blobs with the ones compiled since the last call,
+	/// the names of the replaced and of the unloaded blobs are returned
+	pub fn apply(&self, executor: &mut Executor<S>) -> Vec<String> {
+		let mut updated = Vec::new();
+		for update in self.updates.try_iter() {
+			match update {
+				Update::Compiled(names, blob) =>
+					for name in names {
+						executor.insert(&name, blob.clone());
+						updated.push(name);
+					},
+				Update::Failed(names) =>
+					for name in names {
+						executor.unload(&name);
+						updated.push(name);
+					},
+			}
+		}
+		updated
+	}
+}
+
+impl<S> Drop for Watcher<S> {
+	fn drop(&mut self) {
+		self.stop.store(true, Ordering::Relaxed);
+	}
+}
+
+fn snapshot(dir: &Path) -> Snapshot {
+	let Ok(entries) = fs::read_dir(dir) else { return Snapshot::new() };
+	entries
+		.filter_map(|entry| {
+			let path = entry.ok()?.path();
+			if path.extension()? != "wasm" {
+				return None
+			}
+			let metadata = fs::metadata(&path).ok()?;
+			Some((path, (metadata.modified().ok()?, metadata.len())))
+		})
+		.collect()
+}
+
+// The files added or modified since the previous snapshot, which is replaced
+fn changed(previous: &mut Snapshot, current: Snapshot) -> Vec<PathBuf> {
+	let mut paths: Vec<_> = current
+		.iter()
+		.filter(|(path, file)| previous.get(*path) != Some(file))
+		.map(|(path, _)| path.clone())
+		.collect();
+	paths.sort();
+	*previous = current;
+	paths
+}
+
+fn name(path: &Path) -> String {
+	path.file_stem().unwrap_or_default().to_string_lossy().into_owned()
+}
+
+// Compile the changed wasm code. It replaces the blobs loaded with the file name, for a file of
+// the wasm codes directory, and with the name without extension, unless it is an alias of
+// another wasm code.
+fn reload<S: State>(executor: &Executor<S>, path: &Path, sender: &Sender<Update<S>>) {
+	let name = name(path);
+	let mut names = vec![name.clone()];
+	if path.parent() == Some(executor.wasm_codes().dir()) {
+		names.push(format!("{}.wasm", name));
+	}
+
+	let compiled = fs::read(path)
+		.map_err(|err| err.to_string())
+		.and_then(|wasm_code| executor.compile(wasm_code));
+	let update = match compiled {
+		Ok(blob) => {
+			let identified = executor.wasm_codes().info(&name).map(|info| info.hash);
+			if identified.as_ref() != Ok(&blob.hash) {
+				names.remove(0);
+			}
+			println!("\n[updated: {}]", name);
+			Update::Compiled(names, blob)
+		},
+		Err(err) => {
+			println!("\n[updated: {}, compile error: {}]", name, err);
+			Update::Failed(names)
+		},
+	};
+	// The menu is gone only while the process ends
+	let _ = sender.send(update);
+}
+
+// Add the built wasm code to the wasm codes, with its file name as alias, and compile it
+fn add_built<S: State>(executor: &Executor<S>, path: &Path, sender: &Sender<Update<S>>) {
+	let name = name(path);
+	let added = fs::read(path)
+		.map_err(|err| err.to_string())
+		.and_then(|wasm_code| executor.wasm_codes().add(&wasm_code, Some(&name), None));
+	match added {
+		Ok(info) => {
+			println!("\n[built: {} ({})]", name, &info.hash[..SHORT_HASH_LEN]);
+			reload(executor, path, sender);
+		},
+		Err(err) => println!("\n[built: {}, error: {}]", name, err),
+	}
+}
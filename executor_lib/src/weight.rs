@@ -0,0 +1,116 @@
+//! Weight of the executions: the fuel consumed by the instructions of the wasm code
+//! plus the cost of the Host Functions it calls.
+//!
+//! The fuel counts only the wasm instructions, so a Host Function copying megabytes would
+//! cost the same as one copying a byte. Every Host Function calls `charge` with the number
+//! of bytes it handles, the cost of the function in the table of the Registry
+//! (see `Registry::with_costs`) is consumed from the fuel of the execution. The functions
+//! are identified by namespace and name, so every ABI version can have its own costs.
+
+use crate::host::StoreData;
+use serde::Serialize;
+use std::{
+	collections::{BTreeMap, HashMap},
+	fmt,
+	sync::Arc,
+};
+use wasmtime::{Caller, Trap};
+
+/// A Host Function, identified by the namespace and the name of its import
+pub type HostFunction = (&'static str, &'static str);
+
+/// Cost of a Host Function in fuel: `fixed` for every call plus `per_byte`
+/// for every byte it copies or processes
+#[derive(Clone, Copy, Default, Debug, Serialize)]
+pub struct HostCost {
+	pub fixed: u64,
+	pub per_byte: u64,
+}
+
+impl HostCost {
+	pub const fn new(fixed: u64, per_byte: u64) -> Self {
+		HostCost { fixed, per_byte }
+	}
+
+	fn fuel(&self, bytes: u64) -> u64 {
+		self.fixed.saturating_add(self.per_byte.saturating_mul(bytes))
+	}
+}
+
+/// The calls of a Host Function during an execution
+#[derive(Clone, Default, Serialize)]
+pub struct HostWeight {
+	pub calls: u64,
+	pub bytes: u64,
+	pub fuel: u64,
+}
+
+/// Weight of an execution, all the values are fuel
+#[derive(Clone, Serialize)]
+pub struct Weight {
+	/// Consumed by the wasm instructions
+	pub instructions: u64,
+	/// Charged by the Host Functions
+	pub host: u64,
+	pub total: u64,
+	/// The Host Functions called, as `namespace.name`
+	pub host_functions: BTreeMap<String, HostWeight>,
+}
+
+impl fmt::Display for Weight {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"{} (instructions {}, host functions {})",
+			self.total, self.instructions, self.host
+		)
+	}
+}
+
+/// Costs of the Host Functions and what they charged during an execution, in the Store
+pub(crate) struct Meter {
+	costs: Arc<HashMap<HostFunction, HostCost>>,
+	charged: BTreeMap<String, HostWeight>,
+}
+
+impl Meter {
+	pub(crate) fn new(costs: Arc<HashMap<HostFunction, HostCost>>) -> Self {
+		Meter { costs, charged: BTreeMap::new() }
+	}
+
+	/// Weight of the execution that consumed `fuel_consumed`, host costs included
+	pub(crate) fn weight(self, fuel_consumed: u64) -> Weight {
+		let host = self.charged.values().map(|weight| weight.fuel).sum();
+		Weight {
+			instructions: fuel_consumed.saturating_sub(host),
+			host,
+			total: fuel_consumed,
+			host_functions: self.charged,
+		}
+	}
+}
+
+/// Charge the cost of the Host Function `function` handling `bytes` bytes against the fuel
+/// of the execution, to be called before doing the work. A function without a cost in the
+/// table is free, but its calls are counted anyway. If the fuel is not enough it traps.
+pub fn charge<S>(
+	caller: &mut Caller<'_, StoreData<S>>,
+	function: HostFunction,
+	bytes: usize,
+) -> Result<(), Trap> {
+	let cost = caller.data().meter.costs.get(&function).copied().unwrap_or_default();
+	let fuel = cost.fuel(bytes as u64);
+	caller.consume_fuel(fuel).map_err(|_| Trap::OutOfFuel)?;
+
+	let (namespace, name) = function;
+	let charged = caller
+		.data_mut()
+		.meter
+		.charged
+		.entry(format!("{}.{}", namespace, name))
+		.or_default();
+	charged.calls += 1;
+	charged.bytes += bytes as u64;
+	charged.fuel += fuel;
+	Ok(())
+}
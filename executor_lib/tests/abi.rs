@@ -0,0 +1,101 @@
+//! The ABI versions: a blob compiled against an older version keeps running once a newer
+//! one is registered, and its version is the highest one among its imports
+
+mod common;
+
+use common::{Counter, CounterModule, TempDir};
+use executor_lib::{Executor, HostModule, Registry, StoreData};
+use wasmtime::{Linker, Result};
+
+// `get` returns 1 and `ten` returns 10
+struct VersionOne;
+
+impl HostModule<Counter> for VersionOne {
+	type Data = ();
+
+	fn namespace(&self) -> &'static str {
+		"env_v1"
+	}
+
+	fn functions(&self) -> &'static [&'static str] {
+		&["get", "ten"]
+	}
+
+	fn register(&self, linker: &mut Linker<StoreData<Counter>>) -> Result<()> {
+		linker.func_wrap("env_v1", "get", || 1)?;
+		linker.func_wrap("env_v1", "ten", || 10)?;
+		Ok(())
+	}
+}
+
+// Redefine `get`, returning 2, and inherit `ten`
+struct VersionTwo;
+
+impl HostModule<Counter> for VersionTwo {
+	type Data = ();
+
+	fn namespace(&self) -> &'static str {
+		"env_v2"
+	}
+
+	fn functions(&self) -> &'static [&'static str] {
+		&["get"]
+	}
+
+	fn register(&self, linker: &mut Linker<StoreData<Counter>>) -> Result<()> {
+		linker.func_wrap("env_v2", "get", || 2)?;
+		Ok(())
+	}
+}
+
+// Set the counter to the sum of the results of the imported functions
+fn sum_wat(imports: &[(&str, &str)]) -> String {
+	let calls: String =
+		(0..imports.len()).map(|index| format!("(call $f{}) i32.add ", index)).collect();
+	let imports: String = imports
+		.iter()
+		.enumerate()
+		.map(|(index, (namespace, name))| {
+			format!(r#"(import "{}" "{}" (func $f{} (result i32)))"#, namespace, name, index)
+		})
+		.collect();
+	format!(
+		r#"(module
+			(import "test" "set" (func $set (param i32)))
+			{}
+			(func (export "start") (call $set (i32.const 0) {})))"#,
+		imports, calls
+	)
+}
+
+#[test]
+fn versions() {
+	let dir = TempDir::new("versions");
+	for (name, imports) in [
+		("v1", &[("env_v1", "get"), ("env_v1", "ten")][..]),
+		("legacy", &[("env", "get"), ("env", "ten")]),
+		("v2", &[("env_v2", "get"), ("env_v2", "ten")]),
+		("mixed", &[("env_v1", "get"), ("env_v2", "get")]),
+	] {
+		dir.write_wat(name, &sum_wat(imports));
+	}
+	dir.write_wat("no_abi", &sum_wat(&[]));
+
+	let registry = Registry::new().with(CounterModule).with(VersionOne).with(VersionTwo);
+	assert_eq!(registry.latest_abi_version(), Some(2));
+	let mut executor = Executor::new(dir.wasm_codes(), registry).unwrap();
+
+	// The blobs of the first version, also the legacy ones importing from `env`,
+	// still get the Host Functions of the first version
+	for (name, counter, abi_version) in [
+		("v1", 11, Some(1)),
+		("legacy", 11, Some(1)),
+		("v2", 12, Some(2)),
+		("mixed", 3, Some(2)),
+		("no_abi", 0, None),
+	] {
+		let execution = executor.run(name, Counter(0));
+		assert_eq!(execution.result.unwrap(), Counter(counter), "{}", name);
+		assert_eq!(execution.abi_version, abi_version, "{}", name);
+	}
+}
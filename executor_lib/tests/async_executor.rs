@@ -0,0 +1,110 @@
+//! The AsyncExecutor on tokio: Host Functions awaiting futures and wasm codes yielding
+//! to the other tasks
+
+mod common;
+
+use common::{Counter, CounterModule, TempDir};
+use executor_lib::{AsyncExecutor, HostModule, Registry, StoreData};
+use std::{
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
+use tokio::{sync::Notify, time};
+use wasmtime::{Caller, Linker, Result};
+
+// Set the counter to 1 after `wait.sleep`, then to 2 after `wait.notified`
+const WAIT_WAT: &str = r#"(module
+	(import "test" "set" (func $set (param i32)))
+	(import "wait" "sleep" (func $sleep))
+	(import "wait" "notified" (func $notified))
+	(func (export "start")
+		(call $sleep)
+		(call $set (i32.const 1))
+		(call $notified)
+		(call $set (i32.const 2))))"#;
+
+// Spin for a few hundred milliseconds, within the fuel of an execution
+const SPIN_WAT: &str = r#"(module
+	(func (export "start") (local $iterations i32)
+		(local.set $iterations (i32.const 50000000))
+		(loop $loop
+			(local.set $iterations (i32.sub (local.get $iterations) (i32.const 1)))
+			(br_if $loop (local.get $iterations)))))"#;
+
+// `sleep` awaits a timer, `notified` awaits a notification from another task
+struct Wait(Arc<Notify>);
+
+impl HostModule<Counter> for Wait {
+	type Data = ();
+
+	fn namespace(&self) -> &'static str {
+		"wait"
+	}
+
+	fn functions(&self) -> &'static [&'static str] {
+		&["sleep", "notified"]
+	}
+
+	fn register(&self, linker: &mut Linker<StoreData<Counter>>) -> Result<()> {
+		linker.func_wrap0_async("wait", "sleep", |_: Caller<'_, StoreData<Counter>>| {
+			Box::new(time::sleep(Duration::from_millis(10)))
+		})?;
+		let notify = self.0.clone();
+		linker.func_wrap0_async("wait", "notified", move |_: Caller<'_, StoreData<Counter>>| {
+			let notify = notify.clone();
+			Box::new(async move { notify.notified().await })
+		})?;
+		Ok(())
+	}
+}
+
+#[tokio::test]
+async fn awaiting_host_functions() {
+	let dir = TempDir::new("awaiting_host_functions");
+	dir.write_wat("wait", WAIT_WAT);
+	let notify = Arc::new(Notify::new());
+	let registry = Registry::new().with(CounterModule).with(Wait(notify.clone()));
+	let mut executor = AsyncExecutor::new(dir.wasm_codes(), registry).unwrap();
+
+	// The execution is suspended until the other task notifies it
+	let notifier = tokio::spawn(async move {
+		time::sleep(Duration::from_millis(50)).await;
+		notify.notify_one();
+	});
+	let execution = executor.run("wait", Counter(0)).await;
+	assert_eq!(execution.result.unwrap(), Counter(2));
+	notifier.await.unwrap();
+}
+
+#[tokio::test]
+async fn yielding() {
+	let dir = TempDir::new("yielding");
+	dir.write_wat("spin", SPIN_WAT);
+	let mut executor = AsyncExecutor::new(dir.wasm_codes(), Registry::new()).unwrap();
+	executor.load("spin").unwrap();
+
+	// On the single thread of the runtime the other future, polled after the execution,
+	// makes progress only if the wasm code yields while it spins
+	let done = AtomicBool::new(false);
+	let (execution, polls) = tokio::join!(
+		biased;
+		async {
+			let execution = executor.run("spin", Counter(0)).await;
+			done.store(true, Ordering::Relaxed);
+			execution
+		},
+		async {
+			let mut polls = 0;
+			while !done.load(Ordering::Relaxed) {
+				tokio::task::yield_now().await;
+				polls += 1;
+			}
+			polls
+		}
+	);
+	assert_eq!(execution.result.unwrap(), Counter(0));
+	assert!(polls > 0);
+}
@@ -0,0 +1,40 @@
+//! The backtraces of the failed executions, symbolicated with the name section.
+//! The wat crate does not generate DWARF, so the frames have no source lines.
+
+mod common;
+
+use common::{Counter, CounterModule, TempDir};
+use executor_lib::{Execution, Executor, Registry, Report};
+
+const TRAP_WAT: &str = r#"(module
+	(func $inner unreachable)
+	(func $outer (call $inner))
+	(func $start (export "start") (call $outer)))"#;
+
+fn functions(execution: &Execution<Counter>) -> Vec<String> {
+	let backtrace = execution.backtrace.as_ref().unwrap();
+	assert!(backtrace.0.iter().all(|frame| frame.file.is_none() && frame.line.is_none()));
+	backtrace.0.iter().map(|frame| frame.function.clone()).collect()
+}
+
+#[test]
+fn named_frames() {
+	let dir = TempDir::new("named_frames");
+	dir.write_wat("trap", TRAP_WAT);
+	let registry = Registry::new().with(CounterModule);
+
+	// From the entry point to the function that trapped
+	let mut executor = Executor::new(dir.wasm_codes(), registry.clone()).unwrap();
+	let execution = executor.run("trap", Counter(0));
+	assert_eq!(functions(&execution), ["start", "outer", "inner"]);
+	let err = execution.result.as_ref().unwrap_err();
+	assert!(err.ends_with(&execution.backtrace.as_ref().unwrap().to_string()));
+
+	let report = Report::from_execution("trap", &Counter(0), execution);
+	let report = serde_json::to_value(report).unwrap();
+	assert_eq!(report["backtrace"][2]["function"], "inner");
+
+	// The pooled executor does not read the DWARF, the names are still there
+	let mut pooled = Executor::pooled(dir.wasm_codes(), registry).unwrap();
+	assert_eq!(functions(&pooled.run("trap", Counter(0))), ["start", "outer", "inner"]);
+}
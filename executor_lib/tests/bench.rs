@@ -0,0 +1,57 @@
+//! The statistics of the benchmarks and their output
+
+mod common;
+
+use common::{Counter, CounterModule, TempDir};
+use executor_lib::{
+	bench::{bench_copies, Stats, VEC_SIZES},
+	Executor, Registry,
+};
+use std::time::Duration;
+
+const SET_WAT: &str = r#"(module
+	(import "test" "set" (func $set (param i32)))
+	(func (export "start") (call $set (i32.const 1))))"#;
+
+fn stats(nanos: &[u64]) -> (u64, u64, u64) {
+	let stats = Stats::new(nanos.iter().copied().map(Duration::from_nanos).collect());
+	(stats.min_ns, stats.median_ns, stats.p99_ns)
+}
+
+#[test]
+fn nearest_rank() {
+	// The order of the durations does not matter
+	let hundred: Vec<u64> = (1..=100).rev().collect();
+	assert_eq!(stats(&hundred), (1, 50, 99));
+	assert_eq!(stats(&[30, 10, 20]), (10, 20, 30));
+	assert_eq!(stats(&[10, 20, 30, 40]), (10, 20, 40));
+	assert_eq!(stats(&[7]), (7, 7, 7));
+	assert_eq!(stats(&[]), (0, 0, 0));
+}
+
+#[test]
+fn output() {
+	let dir = TempDir::new("bench_output");
+	dir.write_wat("set", SET_WAT);
+	let executor = Executor::new(dir.wasm_codes(), Registry::new().with(CounterModule)).unwrap();
+
+	let blob = executor.bench("set", &Counter(0), 3).unwrap();
+	let text = blob.to_string();
+	let lines: Vec<_> = text.lines().collect();
+	assert_eq!(lines[0], "Benchmark of set, 3 iterations (us)");
+	let phases: Vec<_> =
+		lines[1..].iter().map(|line| line.split_whitespace().next().unwrap()).collect();
+	assert_eq!(phases, ["phase", "engine", "compile", "link", "instantiate", "call"]);
+
+	let json = serde_json::to_value(&blob).unwrap();
+	for phase in ["engine", "compile", "link", "instantiate", "call"] {
+		let (min, median, p99) =
+			(&json[phase]["min_ns"], &json[phase]["median_ns"], &json[phase]["p99_ns"]);
+		assert!(min.as_u64().unwrap() <= median.as_u64().unwrap(), "{}", phase);
+		assert!(median.as_u64().unwrap() <= p99.as_u64().unwrap(), "{}", phase);
+	}
+
+	let copies = bench_copies(2).unwrap();
+	assert_eq!(copies.iter().map(|copy| copy.size).collect::<Vec<_>>(), VEC_SIZES);
+	assert!(copies[0].to_string().starts_with("read_vec    16        "));
+}
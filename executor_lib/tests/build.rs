@@ -0,0 +1,30 @@
+//! Build of the `wasm_code` workspace, every exercise is a wasm code the executors can load
+
+use executor_lib::build::build;
+use std::path::Path;
+use wasmtime::{Engine, Module};
+
+#[test]
+fn exercises() {
+	let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../wasm_code");
+	let mut wasm_codes = build(&crate_dir, None).expect("the exercises are built");
+	wasm_codes.sort();
+
+	let names: Vec<_> = wasm_codes
+		.iter()
+		.map(|path| path.file_stem().unwrap().to_string_lossy().into_owned())
+		.collect();
+	assert_eq!(names, ["ex1", "ex4", "ex5"]);
+
+	let engine = Engine::default();
+	for path in &wasm_codes {
+		let module = Module::from_file(&engine, path)
+			.unwrap_or_else(|err| panic!("invalid {}: {}", path.display(), err));
+		assert!(module.exports().next().is_some(), "{} exports nothing", path.display());
+	}
+
+	// A single exercise is built alone
+	let wasm_codes = build(&crate_dir, Some("ex4")).expect("the exercise 4 is built");
+	assert_eq!(wasm_codes.len(), 1);
+	assert!(wasm_codes[0].ends_with("ex4.wasm"));
+}
@@ -0,0 +1,52 @@
+//! The loaded blobs are cached: a blob is compiled and linked once, then executed again
+//! until its wasm code changes
+
+mod common;
+
+use common::{Counter, CounterModule, TempDir};
+use executor_lib::{Executor, Registry};
+
+// Add `value` to the counter
+fn add_wat(value: i32) -> String {
+	format!(
+		r#"(module
+			(import "test" "get" (func $get (result i32)))
+			(import "test" "set" (func $set (param i32)))
+			(func (export "start") (call $set (i32.add (call $get) (i32.const {})))))"#,
+		value
+	)
+}
+
+#[test]
+fn cached_blobs() {
+	let dir = TempDir::new("cache");
+	dir.write_wat("add", &add_wat(1));
+	let mut executor =
+		Executor::new(dir.wasm_codes(), Registry::new().with(CounterModule)).unwrap();
+
+	// Refreshed before every execution, as the subcommands and the server do
+	let mut counter = Counter(0);
+	for _ in 0..5 {
+		executor.refresh("add");
+		counter = executor.execute("add", counter).unwrap();
+	}
+	assert_eq!(counter, Counter(5));
+	assert_eq!(executor.compilations(), 1);
+
+	// The same wasm code written again is not compiled again
+	dir.write_wat("add", &add_wat(1));
+	executor.refresh("add");
+	assert_eq!(executor.execute("add", Counter(0)).unwrap(), Counter(1));
+	assert_eq!(executor.compilations(), 1);
+
+	// A changed wasm code is compiled again
+	dir.write_wat("add", &add_wat(10));
+	executor.refresh("add");
+	assert_eq!(executor.execute("add", Counter(0)).unwrap(), Counter(10));
+	assert_eq!(executor.compilations(), 2);
+
+	// A removed one is not executed from the cache
+	std::fs::remove_file(dir.path().join("add.wasm")).unwrap();
+	executor.refresh("add");
+	assert!(executor.execute("add", Counter(0)).is_err());
+}
@@ -0,0 +1,126 @@
+//! Fixtures shared by the integration tests: the SharedStates with the host modules giving
+//! access to them and a temporary directory of wasm codes
+
+// Every test uses only some of the fixtures
+#![allow(dead_code)]
+
+use executor_lib::{read_vec, HostModule, State, StoreData, WasmCodes};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use wasmtime::{Caller, Linker, Result};
+
+/// A number read and written by the wasm code with `test.get` and `test.set`
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Counter(pub i32);
+
+impl State for Counter {
+	fn parse(value: &str) -> Result<Self, String> {
+		value.parse().map(Counter).map_err(|_| format!("invalid counter: {}", value))
+	}
+
+	fn show(&self) -> String {
+		self.0.to_string()
+	}
+}
+
+pub struct CounterModule;
+
+impl HostModule<Counter> for CounterModule {
+	type Data = ();
+
+	fn namespace(&self) -> &'static str {
+		"test"
+	}
+
+	fn functions(&self) -> &'static [&'static str] {
+		&["get", "set"]
+	}
+
+	fn register(&self, linker: &mut Linker<StoreData<Counter>>) -> Result<()> {
+		linker.func_wrap("test", "get", |caller: Caller<'_, StoreData<Counter>>| {
+			caller.data().shared_state.0
+		})?;
+		linker.func_wrap(
+			"test",
+			"set",
+			|mut caller: Caller<'_, StoreData<Counter>>, value: i32| {
+				caller.data_mut().shared_state.0 = value;
+			},
+		)?;
+		Ok(())
+	}
+}
+
+/// The bytes written by the wasm code with `test.set`
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Bytes(pub Vec<u8>);
+
+impl State for Bytes {
+	fn parse(value: &str) -> Result<Self, String> {
+		Ok(Bytes(value.as_bytes().to_vec()))
+	}
+
+	fn show(&self) -> String {
+		format!("{:?}", self.0)
+	}
+}
+
+pub struct SetBytes;
+
+impl HostModule<Bytes> for SetBytes {
+	type Data = ();
+
+	fn namespace(&self) -> &'static str {
+		"test"
+	}
+
+	fn functions(&self) -> &'static [&'static str] {
+		&["set"]
+	}
+
+	fn register(&self, linker: &mut Linker<StoreData<Bytes>>) -> Result<()> {
+		linker.func_wrap(
+			"test",
+			"set",
+			|mut caller: Caller<'_, StoreData<Bytes>>, ptr: u32, size: u32| -> Result<()> {
+				caller.data_mut().shared_state = Bytes(read_vec(&mut caller, ptr, size)?);
+				Ok(())
+			},
+		)?;
+		Ok(())
+	}
+}
+
+/// A directory of wasm codes in the temporary directory, removed when it is dropped
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+	/// The name of the test keeps apart the directories of the tests running in parallel
+	pub fn new(test: &str) -> Self {
+		let dir =
+			std::env::temp_dir().join(format!("executor_lib_{}_{}", test, std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		TempDir(dir)
+	}
+
+	pub fn path(&self) -> &Path {
+		&self.0
+	}
+
+	pub fn wasm_codes(&self) -> WasmCodes {
+		WasmCodes::new(&self.0)
+	}
+
+	/// Write the wasm code of the text format as `<name>.wasm`, in the binary format
+	pub fn write_wat(&self, name: &str, wat: &str) -> PathBuf {
+		let path = self.0.join(format!("{}.wasm", name));
+		std::fs::write(&path, wat::parse_str(wat).unwrap()).unwrap();
+		path
+	}
+}
+
+impl Drop for TempDir {
+	fn drop(&mut self) {
+		let _ = std::fs::remove_dir_all(&self.0);
+	}
+}
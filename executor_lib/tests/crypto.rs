@@ -0,0 +1,150 @@
+//! The cryptographic Host Functions checked against known vectors
+
+mod common;
+
+use common::{Bytes, SetBytes, TempDir};
+use executor_lib::{
+	crypto::{self, SR25519_CONTEXT},
+	Crypto, Executor, Registry,
+};
+
+fn hex(hex: &str) -> Vec<u8> {
+	(0..hex.len())
+		.step_by(2)
+		.map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+		.collect()
+}
+
+#[test]
+fn blake2b_256() {
+	assert_eq!(
+		crypto::blake2b_256(b"").to_vec(),
+		hex("0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8")
+	);
+	assert_eq!(
+		crypto::blake2b_256(b"abc").to_vec(),
+		hex("bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319")
+	);
+}
+
+#[test]
+fn sha2_256() {
+	assert_eq!(
+		crypto::sha2_256(b"").to_vec(),
+		hex("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+	);
+	assert_eq!(
+		crypto::sha2_256(b"abc").to_vec(),
+		hex("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+	);
+}
+
+#[test]
+fn keccak_256() {
+	assert_eq!(
+		crypto::keccak_256(b"").to_vec(),
+		hex("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+	);
+	assert_eq!(
+		crypto::keccak_256(b"abc").to_vec(),
+		hex("4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45")
+	);
+}
+
+#[test]
+fn twox() {
+	// xxHash64 of the empty string is 0xef46db3751d8e999
+	assert_eq!(crypto::twox_64(b"").to_vec(), hex("99e9d85137db46ef"));
+	// Storage prefixes of Substrate
+	assert_eq!(crypto::twox_128(b"System").to_vec(), hex("26aa394eea5630e07c48ae0c9558cef7"));
+	assert_eq!(crypto::twox_128(b"Balances").to_vec(), hex("c2261276cc9d1f8598ea4b6a74b15c2f"));
+	// The hashes with more seeds start with the ones with less
+	assert_eq!(crypto::twox_256(b"System")[..16], crypto::twox_128(b"System"));
+}
+
+// Test vectors 1 and 2 of RFC 8032
+const ED25519_VECTORS: [(&str, &str, &str); 2] = [
+	(
+		"d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+		"",
+		"e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+	),
+	(
+		"3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+		"72",
+		"92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+	),
+];
+
+#[test]
+fn ed25519_verify() {
+	for (public_key, message, signature) in ED25519_VECTORS {
+		let (public_key, message, signature) = (hex(public_key), hex(message), hex(signature));
+		assert!(crypto::ed25519_verify(&signature, &message, &public_key));
+		assert!(!crypto::ed25519_verify(&signature, b"another message", &public_key));
+	}
+
+	// Signature of the first vector with the key of the second one
+	let signature = hex(ED25519_VECTORS[0].2);
+	assert!(!crypto::ed25519_verify(&signature, b"", &hex(ED25519_VECTORS[1].0)));
+	// Malformed signature and public key
+	assert!(!crypto::ed25519_verify(&signature[..63], b"", &hex(ED25519_VECTORS[0].0)));
+	assert!(!crypto::ed25519_verify(&signature, b"", &[0; 31]));
+}
+
+#[test]
+fn sr25519_verify() {
+	// sr25519 signatures are randomized, so they are created with a known secret key
+	let keypair = schnorrkel::MiniSecretKey::from_bytes(&[7; 32])
+		.unwrap()
+		.expand_to_keypair(schnorrkel::ExpansionMode::Ed25519);
+	let public_key = keypair.public.to_bytes();
+	let signature = keypair.sign_simple(SR25519_CONTEXT, b"message").to_bytes();
+
+	assert!(crypto::sr25519_verify(&signature, b"message", &public_key));
+	assert!(!crypto::sr25519_verify(&signature, b"another message", &public_key));
+
+	// Signed with a different context
+	let signature = keypair.sign_simple(b"another context", b"message").to_bytes();
+	assert!(!crypto::sr25519_verify(&signature, b"message", &public_key));
+}
+
+#[test]
+fn host_functions() {
+	let dir = TempDir::new("crypto");
+
+	// The sha2-256 of "abc" followed by the result of the verification of
+	// the first ed25519 vector, written in the SharedState
+	let (public_key, _, signature) = ED25519_VECTORS[0];
+	let escape = |hex: &str| {
+		hex.as_bytes().chunks(2).fold(String::new(), |escaped, byte| {
+			escaped + "\\" + std::str::from_utf8(byte).unwrap()
+		})
+	};
+	let wat = format!(
+		r#"(module
+			(import "env_v1" "sha2_256" (func $sha2_256 (param i32 i32 i32)))
+			(import "env" "ed25519_verify" (func $ed25519_verify (param i32 i32 i32 i32) (result i32)))
+			(import "test" "set" (func $set (param i32 i32)))
+			(memory (export "memory") 1)
+			(data (i32.const 0) "abc")
+			(data (i32.const 100) "{}")
+			(data (i32.const 200) "{}")
+			(func (export "start")
+				(call $sha2_256 (i32.const 0) (i32.const 3) (i32.const 300))
+				(i32.store8 (i32.const 332)
+					(call $ed25519_verify (i32.const 200) (i32.const 0) (i32.const 0) (i32.const 100)))
+				(call $set (i32.const 300) (i32.const 33))))"#,
+		escape(public_key),
+		escape(signature),
+	);
+	dir.write_wat("crypto", &wat);
+
+	let registry = Registry::new().with(Crypto).with(SetBytes);
+	let mut executor = Executor::new(dir.wasm_codes(), registry).unwrap();
+	let state = executor.execute("crypto", Bytes(Vec::new())).unwrap();
+
+	let mut expected = crypto::sha2_256(b"abc").to_vec();
+	expected.push(1);
+	assert_eq!(state.0, expected);
+}
@@ -0,0 +1,68 @@
+//! The time and the random bytes provided to the wasm code: the recorded values and the
+//! providers making an execution reproducible
+
+mod common;
+
+use common::{Counter, CounterModule, TempDir};
+use executor_lib::{Environment, Executor, Provider, Recording, Registry};
+use std::sync::Arc;
+
+// Set the counter to the first 4 random bytes xor the time
+const ENVIRONMENT_WAT: &str = r#"(module
+	(import "env_v1" "now_millis" (func $now_millis (result i64)))
+	(import "env_v1" "random_bytes" (func $random_bytes (param i32 i32)))
+	(import "test" "set" (func $set (param i32)))
+	(memory (export "memory") 1)
+	(func (export "start") (local $now i64)
+		(local.set $now (call $now_millis))
+		(call $random_bytes (i32.const 0) (i32.const 8))
+		(call $set (i32.xor (i32.load (i32.const 0)) (i32.wrap_i64 (local.get $now))))))"#;
+
+// Execute the wasm code with a new executor, as a new process would do
+fn run(dir: &TempDir, provider: Provider) -> (Result<Counter, String>, Option<Recording>) {
+	let registry = Registry::new().with(CounterModule).with(Environment(provider));
+	let execution = Executor::new(dir.wasm_codes(), registry)
+		.unwrap()
+		.run("environment", Counter(0));
+	(execution.result, execution.environment)
+}
+
+#[test]
+fn fixed() {
+	let dir = TempDir::new("fixed");
+	dir.write_wat("environment", ENVIRONMENT_WAT);
+
+	let (result, environment) = run(&dir, Provider::Fixed { now: 5 });
+	assert_eq!(result.unwrap(), Counter(5));
+	let expected = Recording { now_millis: vec![5], random_bytes: vec![vec![0; 8]] };
+	assert_eq!(environment, Some(expected));
+	assert_eq!(run(&dir, Provider::Fixed { now: 5 }).0.unwrap(), Counter(5));
+}
+
+#[test]
+fn seeded() {
+	let dir = TempDir::new("seeded");
+	dir.write_wat("environment", ENVIRONMENT_WAT);
+
+	let first = run(&dir, Provider::Seeded { now: 5, seed: 42 });
+	assert_eq!(run(&dir, Provider::Seeded { now: 5, seed: 42 }), first);
+	assert_ne!(run(&dir, Provider::Seeded { now: 5, seed: 43 }), first);
+}
+
+#[test]
+fn replay() {
+	let dir = TempDir::new("replay");
+	dir.write_wat("environment", ENVIRONMENT_WAT);
+
+	// The values of the real clock and randomness are served again in the same order
+	let (result, environment) = run(&dir, Provider::Real);
+	let recording = environment.unwrap();
+	assert_eq!(recording.now_millis.len(), 1);
+	assert_eq!(recording.random_bytes.len(), 1);
+	let replayed = run(&dir, Provider::Replay(Arc::new(recording.clone())));
+	assert_eq!(replayed, (result, Some(recording)));
+
+	// The execution fails if it requests more values than the recorded ones
+	let (result, _) = run(&dir, Provider::Replay(Arc::default()));
+	assert!(result.unwrap_err().starts_with("no more recorded values of now_millis"));
+}
@@ -0,0 +1,81 @@
+//! The metadata of the custom section `executor_metadata` and the check of the ABI version
+
+mod common;
+
+use common::{Counter, CounterModule, TempDir};
+use executor_lib::{Executor, Metadata, Registry};
+
+// A wasm code with the metadata section `section`
+fn with_metadata(section: &str) -> Vec<u8> {
+	wat::parse_str(format!(
+		r#"(module
+			(@custom "executor_metadata" "{}")
+			(import "test" "set" (func $set (param i32)))
+			(func (export "start") (call $set (i32.const 1))))"#,
+		section
+	))
+	.unwrap()
+}
+
+fn parse(section: &str) -> Result<Option<Metadata>, String> {
+	Metadata::parse(&with_metadata(section))
+}
+
+#[test]
+fn parse_section() {
+	let metadata = parse("name=counter\\nversion=1.2.0\\nabi=1\\nauthor=someone\\n")
+		.unwrap()
+		.unwrap();
+	assert_eq!((metadata.name.as_str(), metadata.version.as_str()), ("counter", "1.2.0"));
+	assert_eq!(metadata.abi, 1);
+	assert_eq!(metadata.to_string(), "counter 1.2.0, ABI v1");
+
+	// Without the section, or in the text format, there are no metadata
+	let without = wat::parse_str(r#"(module (func (export "start")))"#).unwrap();
+	assert!(Metadata::parse(&without).unwrap().is_none());
+	assert!(Metadata::parse(b"(module)").unwrap().is_none());
+}
+
+#[test]
+fn malformed_section() {
+	for (section, error) in [
+		("name=counter\\nversion=1", "missing metadata field: abi"),
+		("name=counter\\nversion=1\\nabi=one", "invalid ABI version: one"),
+		("name=counter\\nversion=1\\nabi=1\\nnot a field", "invalid metadata line: not a field"),
+		("\\ff", "metadata are not UTF-8"),
+	] {
+		assert_eq!(parse(section).err().as_deref(), Some(error), "{}", section);
+	}
+}
+
+#[test]
+fn check_abi() {
+	let metadata = parse("name=counter\\nversion=1\\nabi=2").unwrap().unwrap();
+	assert!(metadata.check_abi(Some(2), Some(2)).is_ok());
+	assert!(metadata.check_abi(None, Some(3)).is_ok());
+	// The ABI is too new for the executor, or the imports are newer than the required ABI
+	assert_eq!(
+		metadata.check_abi(Some(1), Some(1)).unwrap_err(),
+		"incompatible ABI: counter requires v2, the executor supports up to v1"
+	);
+	assert_eq!(
+		metadata.check_abi(Some(3), Some(3)).unwrap_err(),
+		"incompatible ABI: counter requires v2 but it imports Host Functions of v3"
+	);
+
+	// The executor refuses the wasm code before executing it
+	let dir = TempDir::new("check_abi");
+	std::fs::write(
+		dir.path().join("too_new.wasm"),
+		with_metadata("name=too_new\\nversion=1\\nabi=2"),
+	)
+	.unwrap();
+	let mut executor =
+		Executor::new(dir.wasm_codes(), Registry::new().with(CounterModule)).unwrap();
+	let execution = executor.run("too_new", Counter(0));
+	assert!(execution
+		.result
+		.unwrap_err()
+		.starts_with("incompatible ABI: too_new requires v2"));
+	assert_eq!(execution.fuel_consumed, None);
+}
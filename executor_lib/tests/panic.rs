@@ -0,0 +1,58 @@
+//! The panics reported by the wasm code with `panic(message_ptr, message_len, file_ptr,
+//! file_len, line)` before trapping
+
+mod common;
+
+use common::{Counter, CounterModule, TempDir};
+use executor_lib::{Executor, PanicReporting, Registry, Report};
+use wasmtime::Trap;
+
+// Report the panic "boom" at src/lib.rs:7 and trap, as the panic hook of the wasm code does
+const PANIC_WAT: &str = r#"(module
+	(import "env_v1" "panic" (func $panic (param i32 i32 i32 i32 i32)))
+	(memory (export "memory") 1)
+	(data (i32.const 0) "boom")
+	(data (i32.const 16) "src/lib.rs")
+	(func $start (export "start")
+		(call $panic (i32.const 0) (i32.const 4) (i32.const 16) (i32.const 10) (i32.const 7))
+		unreachable))"#;
+
+const TRAP_WAT: &str = r#"(module
+	(func (export "start") unreachable))"#;
+
+fn executor(dir: &TempDir) -> Executor<Counter> {
+	Executor::new(dir.wasm_codes(), Registry::new().with(CounterModule).with(PanicReporting))
+		.unwrap()
+}
+
+#[test]
+fn reported_panic() {
+	let dir = TempDir::new("reported_panic");
+	dir.write_wat("panic", PANIC_WAT);
+
+	let execution = executor(&dir).run("panic", Counter(0));
+	assert_eq!(execution.trap, Some(Trap::UnreachableCodeReached));
+	let panic = execution.panic.as_ref().unwrap();
+	assert_eq!(
+		(panic.message.as_str(), panic.file.as_str(), panic.line),
+		("boom", "src/lib.rs", 7)
+	);
+	let err = execution.result.as_ref().unwrap_err();
+	assert!(err.starts_with("panicked at src/lib.rs:7: boom ("), "{}", err);
+
+	let report = Report::from_execution("panic", &Counter(0), execution);
+	let report = serde_json::to_value(report).unwrap();
+	assert_eq!(report["status"], "trap");
+	assert_eq!(report["panic"]["message"], "boom");
+	assert!(report["error"].as_str().unwrap().contains("boom"));
+}
+
+#[test]
+fn trap_without_panic() {
+	let dir = TempDir::new("trap_without_panic");
+	dir.write_wat("trap", TRAP_WAT);
+
+	let execution = executor(&dir).run("trap", Counter(0));
+	assert_eq!(execution.trap, Some(Trap::UnreachableCodeReached));
+	assert!(execution.panic.is_none());
+}
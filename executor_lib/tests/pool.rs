@@ -0,0 +1,114 @@
+//! Jobs executed by the ThreadPool, with their limits and in submission order
+
+mod common;
+
+use common::{Counter, CounterModule, TempDir};
+use executor_lib::{HostModule, Job, Limits, Registry, StoreData, ThreadPool};
+use std::num::NonZeroUsize;
+use wasmtime::{Linker, Result, Trap};
+
+const INCREMENT_WAT: &str = r#"(module
+	(import "test" "get" (func $get (result i32)))
+	(import "test" "set" (func $set (param i32)))
+	(func (export "start") (call $set (i32.add (call $get) (i32.const 1)))))"#;
+
+const LOOP_WAT: &str = r#"(module
+	(func (export "start") (loop $forever (br $forever))))"#;
+
+const GROW_WAT: &str = r#"(module
+	(memory 1)
+	(func (export "start") (drop (memory.grow (i32.const 10)))))"#;
+
+const PANIC_WAT: &str = r#"(module
+	(import "panic" "panic" (func $panic))
+	(func (export "start") (call $panic)))"#;
+
+// A Host Function with a bug, it panics
+struct Panicking;
+
+impl HostModule<Counter> for Panicking {
+	type Data = ();
+
+	fn namespace(&self) -> &'static str {
+		"panic"
+	}
+
+	fn functions(&self) -> &'static [&'static str] {
+		&["panic"]
+	}
+
+	fn register(&self, linker: &mut Linker<StoreData<Counter>>) -> Result<()> {
+		linker
+			.func_wrap("panic", "panic", || -> Result<()> { panic!("bug in the host function") })?;
+		Ok(())
+	}
+}
+
+#[test]
+fn jobs() {
+	let dir = TempDir::new("pool");
+	for (name, wat) in [("increment", INCREMENT_WAT), ("loop", LOOP_WAT), ("grow", GROW_WAT)] {
+		dir.write_wat(name, wat);
+	}
+
+	let registry = Registry::new().with(CounterModule);
+	let pool = ThreadPool::new(dir.wasm_codes(), registry, NonZeroUsize::new(4)).unwrap();
+
+	let mut jobs: Vec<_> = (0..100).map(|value| Job::new("increment", Counter(value))).collect();
+	jobs.push(
+		Job::new("loop", Counter(0)).with_limits(Limits { fuel: 10_000, memory_bytes: None }),
+	);
+	jobs.push(
+		Job::new("grow", Counter(0))
+			.with_limits(Limits { fuel: 10_000, memory_bytes: Some(65536) }),
+	);
+	jobs.push(Job::new("grow", Counter(0)));
+	jobs.push(Job::new("missing", Counter(0)));
+
+	let executions = pool.run(jobs);
+
+	assert_eq!(executions.len(), 104);
+	for (value, execution) in executions[..100].iter().enumerate() {
+		assert_eq!(execution.result.as_ref().unwrap().0, value as i32 + 1);
+	}
+	assert_eq!(executions[100].trap, Some(Trap::OutOfFuel));
+	assert!(executions[101].result.is_err());
+	assert!(executions[102].result.is_ok());
+	assert!(executions[103].result.is_err());
+	assert_eq!(executions[103].blob_hash, None);
+
+	// A failed load is not cached, the missing blob is loaded once it is added
+	dir.write_wat("missing", INCREMENT_WAT);
+	let executions = pool.run(vec![Job::new("missing", Counter(1))]);
+	assert_eq!(executions[0].result.as_ref().unwrap().0, 2);
+}
+
+#[test]
+fn panicking_host_function() {
+	let dir = TempDir::new("pool_panic");
+	dir.write_wat("increment", INCREMENT_WAT);
+	dir.write_wat("panic", PANIC_WAT);
+
+	let registry = Registry::new().with(CounterModule).with(Panicking);
+	let pool = ThreadPool::new(dir.wasm_codes(), registry, NonZeroUsize::new(2)).unwrap();
+
+	// The panic fails only its job, the worker goes on with the next ones
+	let jobs = (0..10)
+		.map(|value| match value % 3 {
+			0 => Job::new("panic", Counter(value)),
+			_ => Job::new("increment", Counter(value)),
+		})
+		.collect();
+	let executions = pool.run(jobs);
+
+	assert_eq!(executions.len(), 10);
+	for (value, execution) in executions.iter().enumerate() {
+		match value % 3 {
+			0 => assert_eq!(
+				execution.result.as_ref().unwrap_err(),
+				"the execution panicked: bug in the host function"
+			),
+			_ => assert_eq!(execution.result.as_ref().unwrap().0, value as i32 + 1),
+		}
+	}
+}
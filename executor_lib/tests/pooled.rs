@@ -0,0 +1,41 @@
+//! The Executor with the pooling allocator, reusing its preallocated instance slots
+
+mod common;
+
+use common::{Counter, CounterModule, TempDir};
+use executor_lib::{Executor, Registry, POOL_INSTANCE_COUNT, POOL_MEMORY_PAGES};
+
+const INCREMENT_WAT: &str = r#"(module
+	(import "test" "get" (func $get (result i32)))
+	(import "test" "set" (func $set (param i32)))
+	(func (export "start") (call $set (i32.add (call $get) (i32.const 1)))))"#;
+
+// A Linear Memory of `pages` pages
+fn memory_wat(pages: u64) -> String {
+	format!(r#"(module (memory (export "memory") {}) (func (export "start")))"#, pages)
+}
+
+#[test]
+fn pooled() {
+	let dir = TempDir::new("pooled");
+	dir.write_wat("increment", INCREMENT_WAT);
+	dir.write_wat("largest", &memory_wat(POOL_MEMORY_PAGES));
+	dir.write_wat("too_large", &memory_wat(POOL_MEMORY_PAGES + 1));
+
+	let registry = Registry::new().with(CounterModule);
+	let mut executor = Executor::pooled(dir.wasm_codes(), registry).unwrap();
+
+	// More executions than slots, every Store frees its slot when it is dropped
+	let mut counter = Counter(0);
+	for _ in 0..POOL_INSTANCE_COUNT * 4 {
+		counter = executor.execute("increment", counter).unwrap();
+	}
+	assert_eq!(counter, Counter(POOL_INSTANCE_COUNT as i32 * 4));
+
+	assert!(executor.execute("largest", Counter(0)).is_ok());
+	// A Linear Memory larger than a slot can't be instantiated, the execution just fails
+	let execution = executor.run("too_large", Counter(0));
+	assert!(execution.result.is_err());
+	assert_eq!(execution.trap, None);
+	assert!(executor.execute("increment", Counter(1)).is_ok());
+}
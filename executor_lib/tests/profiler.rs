@@ -0,0 +1,69 @@
+//! The profiles of the executions: the sampled stacks in the folded format and the timed
+//! calls of the Host Functions
+
+mod common;
+
+use common::{Counter, CounterModule, TempDir};
+use executor_lib::{Profiler, Registry};
+use std::{fs, time::Duration};
+
+// Call `test.get` 100 times, spin long enough to be sampled, then set the counter
+const PROFILED_WAT: &str = r#"(module
+	(import "test" "get" (func $get (result i32)))
+	(import "test" "set" (func $set (param i32)))
+	(func $spin (param $iterations i32)
+		(loop $loop
+			(local.set $iterations (i32.sub (local.get $iterations) (i32.const 1)))
+			(br_if $loop (local.get $iterations))))
+	(func $start (export "start") (local $calls i32)
+		(loop $loop
+			(drop (call $get))
+			(local.set $calls (i32.add (local.get $calls) (i32.const 1)))
+			(br_if $loop (i32.lt_u (local.get $calls) (i32.const 100))))
+		(call $spin (i32.const 20000000))
+		(call $set (local.get $calls))))"#;
+
+#[test]
+fn profile() {
+	let dir = TempDir::new("profile");
+	dir.write_wat("profiled", PROFILED_WAT);
+	let registry = Registry::new().with(CounterModule);
+	let mut profiler =
+		Profiler::new(dir.wasm_codes(), registry, Duration::from_micros(10)).unwrap();
+
+	let (execution, profile) = profiler.profile("profiled", Counter(0));
+	assert_eq!(execution.result.unwrap(), Counter(100));
+	let profile = profile.unwrap();
+
+	// Every Host Function called is timed
+	let calls: Vec<_> = profile
+		.host_calls
+		.iter()
+		.map(|(name, calls)| (name.as_str(), calls.count))
+		.collect();
+	assert_eq!(calls, [("test.get", 100), ("test.set", 1)]);
+	assert!(profile.host_calls.values().all(|calls| calls.total <= profile.start));
+
+	// A line for every stack: the functions from the entry point separated by `;`,
+	// a space and the number of samples
+	let folded = profile.folded();
+	assert!(!folded.is_empty());
+	let mut samples = 0;
+	for line in folded.lines() {
+		let (stack, count) = line.rsplit_once(' ').unwrap();
+		let functions: Vec<_> = stack.split(';').collect();
+		assert_eq!(functions[0], "start", "{}", line);
+		assert!(functions.iter().all(|function| !function.is_empty() && !function.contains(' ')));
+		samples += count.parse::<u64>().unwrap();
+	}
+	assert_eq!(samples, profile.stacks.values().sum::<u64>());
+	assert!(folded.contains("start;spin "));
+
+	// The files are written in the output directory
+	let output = dir.path().join("profiles");
+	let files = profile.write(&output).unwrap();
+	assert_eq!(fs::read_to_string(&files[0]).unwrap(), folded);
+	assert!(files[0].ends_with("profiled.folded"));
+	let firefox = fs::read_to_string(&files[1]).unwrap();
+	serde_json::from_str::<serde_json::Value>(&firefox).unwrap();
+}
@@ -0,0 +1,65 @@
+//! The Linker composed by the Registry from the host modules
+
+mod common;
+
+use common::{Counter, CounterModule, TempDir};
+use executor_lib::{Executor, HostModule, Registry, StoreData};
+use wasmtime::{Linker, Result};
+
+const START_WAT: &str = r#"(module (func (export "start")))"#;
+
+// A host module not implemented yet
+struct Unimplemented;
+
+impl HostModule<Counter> for Unimplemented {
+	type Data = ();
+
+	fn namespace(&self) -> &'static str {
+		"todo"
+	}
+
+	fn functions(&self) -> &'static [&'static str] {
+		&["todo"]
+	}
+
+	fn register(&self, _linker: &mut Linker<StoreData<Counter>>) -> Result<()> {
+		todo!()
+	}
+}
+
+fn run(registry: Registry<Counter>, test: &str) -> Result<Counter, String> {
+	let dir = TempDir::new(test);
+	dir.write_wat("start", START_WAT);
+	Executor::new(dir.wasm_codes(), registry)
+		.unwrap()
+		.run("start", Counter(0))
+		.result
+}
+
+#[test]
+fn registered_modules() {
+	let registry = Registry::new().with(CounterModule).with(Unimplemented);
+	let modules: Vec<_> = registry.modules().collect();
+	assert_eq!(modules, [("test", &["get", "set"][..]), ("todo", &["todo"][..])]);
+
+	assert_eq!(run(Registry::new().with(CounterModule), "registered_modules"), Ok(Counter(0)));
+}
+
+#[test]
+fn duplicated_function() {
+	let registry = Registry::new().with(CounterModule).with(CounterModule);
+	assert_eq!(
+		run(registry, "duplicated_function").unwrap_err(),
+		"host function test.get defined twice"
+	);
+}
+
+#[test]
+fn panicking_register() {
+	// The panic fails the executions, not the executor
+	let registry = Registry::new().with(CounterModule).with(Unimplemented);
+	assert_eq!(
+		run(registry, "panicking_register").unwrap_err(),
+		"impossible register the host functions of todo: not yet implemented"
+	);
+}
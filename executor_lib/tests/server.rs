@@ -0,0 +1,118 @@
+//! The JSON-RPC service, served on a free port and called by an in-process client
+
+mod common;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use common::{CounterModule, TempDir};
+use executor_lib::{loopback_addr, HttpServer, Logging, Registry, Server, MAX_EXECUTIONS};
+use serde_json::{json, Value};
+use std::thread;
+
+// Double the counter and log "doubled"
+const DOUBLE_WAT: &str = r#"(module
+	(import "test" "get" (func $get (result i32)))
+	(import "test" "set" (func $set (param i32)))
+	(import "env_v1" "log" (func $log (param i32 i32)))
+	(memory (export "memory") 1)
+	(data (i32.const 0) "doubled")
+	(func (export "start")
+		(call $set (i32.mul (call $get) (i32.const 2)))
+		(call $log (i32.const 0) (i32.const 7))))"#;
+
+// JSON-RPC client of the server at `url`
+struct Client {
+	url: String,
+	next_id: u64,
+}
+
+impl Client {
+	fn call(&mut self, method: &str, params: Value) -> Value {
+		self.next_id += 1;
+		let request =
+			json!({ "jsonrpc": "2.0", "method": method, "params": params, "id": self.next_id });
+		let response: Value =
+			ureq::post(&self.url).send_json(request).unwrap().into_json().unwrap();
+		assert_eq!(response["id"], self.next_id);
+		response
+	}
+}
+
+#[test]
+fn json_rpc() {
+	let dir = TempDir::new("server");
+
+	let registry = Registry::new().with(CounterModule).with(Logging);
+	let server = Server::new(dir.wasm_codes(), registry).unwrap();
+	let http = HttpServer::bind(server, "127.0.0.1:0").unwrap();
+	let mut client = Client { url: format!("http://{}", http.local_addr().unwrap()), next_id: 0 };
+	thread::spawn(move || http.serve());
+
+	let double = wat::parse_str(DOUBLE_WAT).unwrap();
+	let upload = client.call("upload", json!({ "name": "double", "code": BASE64.encode(&double) }));
+	assert_eq!(upload["result"]["name"], "double");
+	let hash = upload["result"]["hash"].as_str().unwrap().to_string();
+	// An invalid wasm code is refused
+	let invalid =
+		client.call("upload", json!({ "name": "invalid", "code": BASE64.encode("(module") }));
+	assert_eq!(invalid["error"]["code"], -32000);
+
+	assert_eq!(client.call("list", Value::Null)["result"], json!(["double"]));
+	let info = client.call("info", json!({ "name": "double" }));
+	assert_eq!(info["result"]["hash"], hash);
+	assert_eq!(info["result"]["exports"], json!(["memory", "start"]));
+
+	let execute = client.call("execute", json!({ "blob": "double", "state": "21" }));
+	assert_eq!(execute["result"]["report"]["output"], 42);
+	let id = execute["result"]["id"].clone();
+
+	// The same wasm code is executed by hash
+	let by_hash = client.call("execute", json!({ "blob": &hash[..8], "state": "2" }));
+	assert_eq!(by_hash["result"]["report"]["output"], 4);
+
+	let result = client.call("result", json!({ "id": id }));
+	assert_eq!(result["result"]["status"], "success");
+	assert_eq!(result["result"]["input"], 21);
+	assert_eq!(client.call("logs", json!({ "id": id }))["result"], json!(["doubled"]));
+
+	let missing = client.call("execute", json!({ "blob": "missing", "state": "1" }));
+	assert_eq!(missing["result"]["report"]["status"], "error");
+	assert_eq!(client.call("result", json!({ "id": 99 }))["error"]["code"], -32000);
+	assert_eq!(client.call("execute", json!({ "blob": "double" }))["error"]["code"], -32602);
+	assert_eq!(client.call("unknown", Value::Null)["error"]["code"], -32601);
+	// A name is never a path out of the directory
+	let path = client.call("info", json!({ "name": "../double" }));
+	assert_eq!(path["error"]["message"], "invalid name");
+}
+
+#[test]
+fn loopback_only() {
+	let dir = TempDir::new("loopback");
+	let server = Server::new(dir.wasm_codes(), Registry::new().with(CounterModule));
+	let err = HttpServer::bind(server.unwrap(), "0.0.0.0:0").err().unwrap();
+	assert_eq!(err, "not a loopback address: 0.0.0.0:0");
+	assert!(loopback_addr("localhost:0").is_ok());
+	assert!(loopback_addr("[::1]:0").is_ok());
+}
+
+#[test]
+fn last_executions() {
+	let dir = TempDir::new("executions");
+	let registry = Registry::new().with(CounterModule).with(Logging);
+	let mut server = Server::new(dir.wasm_codes(), registry).unwrap();
+	let request = |method: &str, params: Value| {
+		json!({ "jsonrpc": "2.0", "method": method, "params": params, "id": 1 }).to_string()
+	};
+
+	let double = wat::parse_str(DOUBLE_WAT).unwrap();
+	server.handle(&request("upload", json!({ "name": "double", "code": BASE64.encode(&double) })));
+	for id in 0..=MAX_EXECUTIONS {
+		let execute = server.handle(&request("execute", json!({ "blob": "double", "state": "1" })));
+		assert_eq!(execute["result"]["id"], id);
+	}
+
+	// The oldest report is forgotten, the ids are not reused
+	let oldest = server.handle(&request("result", json!({ "id": 0 })));
+	assert_eq!(oldest["error"]["message"], "unknown execution: 0");
+	let last = server.handle(&request("result", json!({ "id": MAX_EXECUTIONS })));
+	assert_eq!(last["result"]["output"], 2);
+}
@@ -0,0 +1,83 @@
+//! Runtime upgrades: the code proposed with `set_code` is validated after the execution
+//! and added to the wasm codes, so any executor can execute it by name
+
+mod common;
+
+use common::{Counter, CounterModule, TempDir};
+use executor_lib::{Executor, Registry, SetCode};
+
+// Set the counter to `value`
+fn set_wat(value: i32) -> String {
+	format!(
+		r#"(module
+			(import "test" "set" (func $set (param i32)))
+			(func (export "start") (call $set (i32.const {}))))"#,
+		value
+	)
+}
+
+// Set the counter to 1 and propose `wasm_code` as the new code
+fn upgrade_wat(wasm_code: &[u8]) -> String {
+	let data: String = wasm_code.iter().map(|byte| format!("\\{:02x}", byte)).collect();
+	format!(
+		r#"(module
+			(import "test" "set" (func $set (param i32)))
+			(import "env_v1" "set_code" (func $set_code (param i32 i32)))
+			(memory (export "memory") 1)
+			(data (i32.const 0) "{}")
+			(func (export "start")
+				(call $set (i32.const 1))
+				(call $set_code (i32.const 0) (i32.const {}))))"#,
+		data,
+		wasm_code.len()
+	)
+}
+
+fn executor(dir: &TempDir) -> Executor<Counter> {
+	Executor::new(dir.wasm_codes(), Registry::new().with(CounterModule).with(SetCode)).unwrap()
+}
+
+#[test]
+fn upgrade() {
+	let dir = TempDir::new("upgrade");
+	let new_code = wat::parse_str(set_wat(10)).unwrap();
+	dir.write_wat("upgrade", &upgrade_wat(&new_code));
+
+	let execution = executor(&dir).run("upgrade.wasm", Counter(0));
+	assert_eq!(execution.result.unwrap(), Counter(1));
+	let upgrade = execution.upgrade.unwrap();
+	assert!(upgrade.starts_with("upgrade@"));
+
+	// The new code is in the wasm codes, a new executor, e.g. of a new process, executes it
+	assert!(dir.wasm_codes().list().unwrap().contains(&upgrade));
+	assert_eq!(executor(&dir).execute(&upgrade, Counter(0)).unwrap(), Counter(10));
+}
+
+#[test]
+fn invalid_upgrades() {
+	let dir = TempDir::new("invalid_upgrades");
+	let no_start = wat::parse_str(r#"(module (func (export "begin")))"#).unwrap();
+	let too_new = wat::parse_str(
+		r#"(module
+			(@custom "executor_metadata" "name=too_new\nversion=1\nabi=99")
+			(func (export "start")))"#,
+	)
+	.unwrap();
+	dir.write_wat("garbage", &upgrade_wat(b"not a wasm code"));
+	dir.write_wat("no_start", &upgrade_wat(&no_start));
+	dir.write_wat("too_new", &upgrade_wat(&too_new));
+
+	// The whole execution fails and nothing is added to the wasm codes
+	let mut executor = executor(&dir);
+	for (name, error) in [
+		("garbage", "invalid code upgrade: "),
+		("no_start", "invalid code upgrade: missing entry point: fn start()"),
+		("too_new", "invalid code upgrade: incompatible ABI: too_new requires v99"),
+	] {
+		let execution = executor.run(name, Counter(0));
+		let err = execution.result.unwrap_err();
+		assert!(err.starts_with(error), "{}: {}", name, err);
+		assert_eq!(execution.upgrade, None);
+	}
+	assert_eq!(dir.wasm_codes().list().unwrap(), ["garbage.wasm", "no_start.wasm", "too_new.wasm"]);
+}
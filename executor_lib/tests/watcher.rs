@@ -0,0 +1,45 @@
+//! Hot reload of the wasm codes changed while an executor is running
+
+mod common;
+
+use common::{Counter, CounterModule, TempDir};
+use executor_lib::{Executor, Registry, Watcher};
+use std::{
+	thread,
+	time::{Duration, Instant},
+};
+
+// Set the counter to `value`, the size of the wasm code changes with the number of digits
+fn set_wat(value: i32) -> String {
+	format!(
+		r#"(module
+			(import "test" "set" (func $set (param i32)))
+			(func (export "start") (call $set (i32.const {}))))"#,
+		value
+	)
+}
+
+#[test]
+fn changed_wasm_code() {
+	let dir = TempDir::new("changed_wasm_code");
+	dir.write_wat("counter", &set_wat(1));
+	let mut executor =
+		Executor::new(dir.wasm_codes(), Registry::new().with(CounterModule)).unwrap();
+	assert_eq!(executor.execute("counter", Counter(0)).unwrap(), Counter(1));
+
+	let watcher = Watcher::start(&executor, None);
+	dir.write_wat("counter", &set_wat(1000));
+
+	// The watcher polls the directory, the new code is compiled by its own thread
+	let deadline = Instant::now() + Duration::from_secs(10);
+	let updated = loop {
+		let updated = watcher.apply(&mut executor);
+		if !updated.is_empty() || Instant::now() > deadline {
+			break updated
+		}
+		thread::sleep(Duration::from_millis(50));
+	};
+	assert_eq!(updated, ["counter", "counter.wasm"]);
+	assert_eq!(executor.execute("counter", Counter(0)).unwrap(), Counter(1000));
+	assert_eq!(executor.compilations(), 1);
+}
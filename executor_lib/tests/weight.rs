@@ -0,0 +1,57 @@
+//! The costs of the Host Functions, charged against the fuel of the execution
+
+mod common;
+
+use common::{Counter, TempDir};
+use executor_lib::{Execution, Executor, HostCost, HostFunction, Logging, Registry};
+use wasmtime::Trap;
+
+// Log "hello", 5 bytes
+const LOG_WAT: &str = r#"(module
+	(import "env_v1" "log" (func $log (param i32 i32)))
+	(memory (export "memory") 1)
+	(data (i32.const 0) "hello")
+	(func (export "start") (call $log (i32.const 0) (i32.const 5))))"#;
+
+fn run(dir: &TempDir, costs: &[(HostFunction, HostCost)]) -> Execution<Counter> {
+	let registry = Registry::new().with(Logging).with_costs(costs);
+	Executor::new(dir.wasm_codes(), registry).unwrap().run("log", Counter(0))
+}
+
+#[test]
+fn charged_costs() {
+	let dir = TempDir::new("charged_costs");
+	dir.write_wat("log", LOG_WAT);
+
+	let free = run(&dir, &[]);
+	let charged = run(&dir, &[(("env_v1", "log"), HostCost::new(100, 2))]);
+	assert_eq!(charged.fuel_consumed.unwrap() - free.fuel_consumed.unwrap(), 110);
+
+	let weight = charged.weight.unwrap();
+	assert_eq!(weight.host, 110);
+	assert_eq!(weight.instructions + weight.host, weight.total);
+	assert_eq!(weight.total, charged.fuel_consumed.unwrap());
+	let log = &weight.host_functions["env_v1.log"];
+	assert_eq!((log.calls, log.bytes, log.fuel), (1, 5, 110));
+}
+
+#[test]
+fn costs_of_the_namespace() {
+	let dir = TempDir::new("costs_of_the_namespace");
+	dir.write_wat("log", LOG_WAT);
+
+	// The cost of a function of another namespace is not charged, the call is counted anyway
+	let weight = run(&dir, &[(("env_v2", "log"), HostCost::new(100, 2))]).weight.unwrap();
+	assert_eq!(weight.host, 0);
+	assert_eq!(weight.host_functions["env_v1.log"].calls, 1);
+}
+
+#[test]
+fn over_budget() {
+	let dir = TempDir::new("over_budget");
+	dir.write_wat("log", LOG_WAT);
+
+	let execution = run(&dir, &[(("env_v1", "log"), HostCost::new(u64::MAX, 0))]);
+	assert_eq!(execution.trap, Some(Trap::OutOfFuel));
+	assert!(execution.logs.is_empty());
+}
@@ -0,0 +1,87 @@
+//! Wrappers around the cryptographic host functions of the executor,
+//! so the wasm code can hash and verify signatures without `unsafe` and raw pointers.
+//!
+//! Hashing in wasm is slow, the executor does it natively: the data is passed as pointer
+//! and size and the executor writes the hash in the provided array.
+
+#[link(wasm_import_module = "env_v1")]
+extern "C" {
+	fn blake2b_256(ptr: u32, size: u32, out_ptr: u32);
+	fn sha2_256(ptr: u32, size: u32, out_ptr: u32);
+	fn keccak_256(ptr: u32, size: u32, out_ptr: u32);
+	fn twox_64(ptr: u32, size: u32, out_ptr: u32);
+	fn twox_128(ptr: u32, size: u32, out_ptr: u32);
+	fn twox_256(ptr: u32, size: u32, out_ptr: u32);
+	fn ed25519_verify(
+		signature_ptr: u32,
+		message_ptr: u32,
+		message_len: u32,
+		public_key_ptr: u32,
+	) -> u32;
+	fn sr25519_verify(
+		signature_ptr: u32,
+		message_ptr: u32,
+		message_len: u32,
+		public_key_ptr: u32,
+	) -> u32;
+}
+
+// Call a hash host function writing a hash of N bytes
+fn hash<const N: usize>(
+	data: &[u8],
+	host_function: unsafe extern "C" fn(u32, u32, u32),
+) -> [u8; N] {
+	let mut out = [0; N];
+	unsafe { host_function(data.as_ptr() as u32, data.len() as u32, out.as_mut_ptr() as u32) };
+	out
+}
+
+pub fn blake2b_256_hf(data: &[u8]) -> [u8; 32] {
+	hash(data, blake2b_256)
+}
+
+pub fn sha2_256_hf(data: &[u8]) -> [u8; 32] {
+	hash(data, sha2_256)
+}
+
+pub fn keccak_256_hf(data: &[u8]) -> [u8; 32] {
+	hash(data, keccak_256)
+}
+
+pub fn twox_64_hf(data: &[u8]) -> [u8; 8] {
+	hash(data, twox_64)
+}
+
+pub fn twox_128_hf(data: &[u8]) -> [u8; 16] {
+	hash(data, twox_128)
+}
+
+pub fn twox_256_hf(data: &[u8]) -> [u8; 32] {
+	hash(data, twox_256)
+}
+
+// The signatures are 64 bytes and the public keys 32 bytes, both for ed25519 and sr25519
+pub fn ed25519_verify_hf(signature: &[u8; 64], message: &[u8], public_key: &[u8; 32]) -> bool {
+	let valid = unsafe {
+		ed25519_verify(
+			signature.as_ptr() as u32,
+			message.as_ptr() as u32,
+			message.len() as u32,
+			public_key.as_ptr() as u32,
+		)
+	};
+	valid == 1
+}
+
+// The sr25519 signatures are verified with the signing context "substrate"
+pub fn sr25519_verify_hf(signature: &[u8; 64], message: &[u8], public_key: &[u8; 32]) -> bool {
+	let valid = unsafe {
+		sr25519_verify(
+			signature.as_ptr() as u32,
+			message.as_ptr() as u32,
+			message.len() as u32,
+			public_key.as_ptr() as u32,
+		)
+	};
+	valid == 1
+}
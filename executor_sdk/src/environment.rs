@@ -0,0 +1,22 @@
+//! Wrappers around the time and randomness host functions of the executor.
+//!
+//! `wasm32-unknown-unknown` has neither a clock nor a source of randomness, the executor
+//! provides them and can replay an execution giving it the same time and the same bytes.
+
+#[link(wasm_import_module = "env_v1")]
+extern "C" {
+	fn now_millis() -> u64;
+	fn random_bytes(ptr: u32, size: u32);
+}
+
+// Milliseconds since the UNIX epoch
+pub fn now_millis_hf() -> u64 {
+	unsafe { now_millis() }
+}
+
+// N random bytes, the executor provides at most 64 KiB with a single call
+pub fn random_bytes_hf<const N: usize>() -> [u8; N] {
+	let mut bytes = [0; N];
+	unsafe { random_bytes(bytes.as_mut_ptr() as u32, N as u32) };
+	bytes
+}
@@ -0,0 +1,43 @@
+//! Guest side of the ABI between the executors and the wasm blobs.
+//!
+//! The Host Functions are imports of the wasm code, calling them requires `unsafe` and passing
+//! everything as pointers in the Linear Memory. This crate declares all the Host Functions
+//! provided by the executors and wraps them in safe functions, a wasm code only has to
+//! depend on it:
+//!
+//! ```ignore
+//! use executor_sdk::vec_state::{get_vec_hf, set_vec_hf};
+//!
+//! executor_sdk::metadata!(name: "mul_vec_3", abi: 1);
+//! executor_sdk::start!(mul_vec_3);
+//!
+//! fn mul_vec_3() {
+//!     let vec: Vec<u8> = get_vec_hf().iter().map(|v| v * 3).collect();
+//!     set_vec_hf(&vec);
+//! }
+//! ```
+//!
+//! A Host Function is linked only if the wasm code calls it, so a wasm code using only
+//! the functions of one executor can be executed by that executor.
+
+pub mod crypto;
+pub mod environment;
+pub mod logging;
+mod metadata;
+pub mod panic;
+pub mod state;
+pub mod upgrade;
+pub mod vec_state;
+
+/// Define the entry point `start` called by the executors, it installs the panic hook
+/// that reports the panics to the executor (see `panic::set_hook`) and calls `$main`
+#[macro_export]
+macro_rules! start {
+	($main:path) => {
+		#[no_mangle]
+		pub extern "C" fn start() {
+			$crate::panic::set_hook();
+			$main()
+		}
+	};
+}
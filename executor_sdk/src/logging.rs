@@ -0,0 +1,10 @@
+//! Messages reported to the executor, they are shown after the execution
+
+#[link(wasm_import_module = "env_v1")]
+extern "C" {
+	fn log(ptr: u32, size: u32);
+}
+
+pub fn log_hf(message: &str) {
+	unsafe { log(message.as_ptr() as u32, message.len() as u32) }
+}
@@ -0,0 +1,42 @@
+//! Metadata of the wasm code: its name, its version and the ABI version of the host functions
+//! it requires. The metadata are embedded in the custom section `executor_metadata`,
+//! the executor reads them before instantiating the wasm code and refuses it if it does not
+//! support the required ABI version.
+//!
+//! The content of the section is a line `key=value` for each field, for example:
+//!
+//! ```ignore
+//! executor_sdk::metadata!(name: "mul_vec_3", abi: 1);
+//! ```
+//!
+//! The version is the one in `Cargo.toml`, but it can be also provided with `version: "1.0.0"`.
+#[macro_export]
+macro_rules! metadata {
+	(name: $name:literal, version: $version:literal, abi: $abi:literal $(,)?) => {
+		$crate::metadata!(@section concat!(
+			"name=", $name, "\nversion=", $version, "\nabi=", $abi, "\n"
+		));
+	};
+	(name: $name:literal, abi: $abi:literal $(,)?) => {
+		$crate::metadata!(@section concat!(
+			"name=", $name, "\nversion=", env!("CARGO_PKG_VERSION"), "\nabi=", $abi, "\n"
+		));
+	};
+	(@section $metadata:expr) => {
+		const METADATA: &str = $metadata;
+
+		// The custom section is created from the bytes of a static in it,
+		// the string needs to be copied in an array because a static can't contain a reference
+		#[used]
+		#[link_section = "executor_metadata"]
+		static METADATA_SECTION: [u8; METADATA.len()] = {
+			let mut section = [0; METADATA.len()];
+			let mut i = 0;
+			while i < section.len() {
+				section[i] = METADATA.as_bytes()[i];
+				i += 1;
+			}
+			section
+		};
+	};
+}
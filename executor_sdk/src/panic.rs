@@ -0,0 +1,46 @@
+//! Report of the panics to the executor.
+//!
+//! A panic in wasm ends the execution with an `unreachable` trap, which says nothing about
+//! what happened. The hook installed by `start!` reports the panic message and its location
+//! with the `panic` Host Function before the trap, the executor shows them in its error.
+
+use std::panic::{self, PanicHookInfo};
+
+#[link(wasm_import_module = "env_v1")]
+extern "C" {
+	#[link_name = "panic"]
+	fn report_panic(message_ptr: u32, message_len: u32, file_ptr: u32, file_len: u32, line: u32);
+}
+
+/// Install the hook reporting the panics to the executor
+pub fn set_hook() {
+	panic::set_hook(Box::new(report));
+}
+
+/// Report a panic to the executor, the message and the file are passed as pointer and size
+pub fn report_panic_hf(message: &str, file: &str, line: u32) {
+	unsafe {
+		report_panic(
+			message.as_ptr() as u32,
+			message.len() as u32,
+			file.as_ptr() as u32,
+			file.len() as u32,
+			line,
+		)
+	}
+}
+
+fn report(info: &PanicHookInfo) {
+	let message = if let Some(message) = info.payload().downcast_ref::<&str>() {
+		message
+	} else if let Some(message) = info.payload().downcast_ref::<String>() {
+		message.as_str()
+	} else {
+		"Box<dyn Any>"
+	};
+
+	match info.location() {
+		Some(location) => report_panic_hf(message, location.file(), location.line()),
+		None => report_panic_hf(message, "<unknown>", 0),
+	}
+}
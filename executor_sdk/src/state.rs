@@ -0,0 +1,15 @@
+//! The SharedState of `rust_executor`, a single `u32`
+
+#[link(wasm_import_module = "env_v1")]
+extern "C" {
+	fn get() -> u32;
+	fn set(val: u32);
+}
+
+pub fn get_hf() -> u32 {
+	unsafe { get() }
+}
+
+pub fn set_hf(val: u32) {
+	unsafe { set(val) }
+}
@@ -0,0 +1,12 @@
+//! Runtime upgrade: the wasm code can propose new wasm bytes that replace it after a
+//! successful execution
+
+#[link(wasm_import_module = "env_v1")]
+extern "C" {
+	fn set_code(ptr: u32, size: u32);
+}
+
+/// Propose the new code, the last one proposed during the execution is used
+pub fn set_code_hf(wasm_code: &[u8]) {
+	unsafe { set_code(wasm_code.as_ptr() as u32, wasm_code.len() as u32) }
+}
@@ -0,0 +1,49 @@
+//! The SharedState of `rust_advanced_executor`, a vector of bytes.
+//!
+//! Vectors can't be passed directly to the Host Functions: `set_vec` accepts the pointer
+//! of the vector in the Linear Memory and its size, the executor reads the vector and copies
+//! it in the SharedState. `get_vec` instead accepts the pointer and the size of free space
+//! where the executor writes the vector.
+
+#[link(wasm_import_module = "env_v1")]
+extern "C" {
+	fn set_vec(ptr: u32, size: u32);
+	fn get_vec(ptr: u32, max_size: u32) -> u32;
+}
+
+mod v2 {
+	#[link(wasm_import_module = "env_v2")]
+	extern "C" {
+		pub fn get_vec(ptr: u32, max_size: u32) -> u32;
+	}
+}
+
+/// Size of the free space provided to `get_vec`, a longer SharedState traps
+pub const MAX_SIZE: usize = 100;
+
+pub fn set_vec_hf(vec: &[u8]) {
+	unsafe { set_vec(vec.as_ptr() as u32, vec.len() as u32) }
+}
+
+/// The SharedState, at most `MAX_SIZE` bytes
+pub fn get_vec_hf() -> Vec<u8> {
+	let mut vec = vec![0; MAX_SIZE];
+	// The Host Function returns the size of the written vector
+	let size = unsafe { get_vec(vec.as_mut_ptr() as u32, MAX_SIZE as u32) };
+	vec.truncate(size as usize);
+	vec
+}
+
+/// The whole SharedState, with the `get_vec` of ABI v2: it returns the size of the
+/// vector and writes it only if it fits, so a longer SharedState is requested again
+/// with enough space
+pub fn get_vec_v2_hf() -> Vec<u8> {
+	let mut vec = vec![0; MAX_SIZE];
+	let size = unsafe { v2::get_vec(vec.as_mut_ptr() as u32, vec.len() as u32) } as usize;
+	if size > vec.len() {
+		vec.resize(size, 0);
+		unsafe { v2::get_vec(vec.as_mut_ptr() as u32, size as u32) };
+	}
+	vec.truncate(size);
+	vec
+}
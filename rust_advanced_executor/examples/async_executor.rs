@@ -0,0 +1,105 @@
+//! The Advanced Executor on tokio: the blocking menu is replaced by an async loop reading
+//! the commands from stdin, and `get_vec`/`set_vec` are async Host Functions.
+//!
+//! Run it from `rust_advanced_executor` with `cargo run --example async_executor`, the
+//! commands are `run <wasm_code>`, `state <bytes>`, `list` and `quit`.
+
+use executor_lib::{
+	AsyncExecutor, Crypto, Environment, HostModule, Logging, PanicReporting, Provider, Registry,
+	SetCode, State, StoreData, WasmCodes, WASM_CODES_DIR,
+};
+use rust_advanced_executor::{get_vec, set_vec, SharedState, StateModuleV2, HOST_COSTS};
+use tokio::io::{self, AsyncBufReadExt, BufReader};
+use wasmtime::{Caller, Linker, Result};
+
+// Same Host Functions of `StateModule`, defined as async functions: the future
+// is awaited by the executor while the wasm code is suspended, so they could read and
+// write the SharedState in an async store
+struct AsyncStateModule;
+
+impl HostModule<SharedState> for AsyncStateModule {
+	type Data = ();
+
+	fn namespace(&self) -> &'static str {
+		"env_v1"
+	}
+
+	fn functions(&self) -> &'static [&'static str] {
+		&["set_vec", "get_vec"]
+	}
+
+	fn register(&self, linker: &mut Linker<StoreData<SharedState>>) -> Result<()> {
+		linker.func_wrap2_async(
+			"env_v1",
+			"set_vec",
+			|mut caller: Caller<'_, StoreData<SharedState>>, ptr: u32, size: u32| {
+				Box::new(async move { set_vec(&mut caller, ptr, size) })
+			},
+		)?;
+
+		linker.func_wrap2_async(
+			"env_v1",
+			"get_vec",
+			|mut caller: Caller<'_, StoreData<SharedState>>, ptr: u32, size: u32| {
+				Box::new(async move { get_vec(&mut caller, ptr, size) })
+			},
+		)?;
+
+		Ok(())
+	}
+}
+
+#[tokio::main]
+async fn main() -> Result<(), String> {
+	let registry = Registry::new()
+		.with(AsyncStateModule)
+		.with(Logging)
+		.with(PanicReporting)
+		.with(SetCode)
+		.with(Crypto)
+		.with(StateModuleV2)
+		.with(Environment(Provider::Real))
+		.with_costs(HOST_COSTS);
+
+	let wasm_codes = WasmCodes::new(WASM_CODES_DIR);
+	let mut executor = AsyncExecutor::new(wasm_codes.clone(), registry)?;
+	let mut state = SharedState { val: vec![1, 2, 3] };
+
+	println!("Current SharedState value: {}", state.show());
+	let mut lines = BufReader::new(io::stdin()).lines();
+	while let Some(line) = lines.next_line().await.map_err(|err| err.to_string())? {
+		let (command, argument) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+		match command {
+			"run" => {
+				// The wasm code could be changed since the last execution
+				executor.refresh(argument);
+				let execution = executor.run(argument, state.clone()).await;
+				for log in &execution.logs {
+					println!("log: {}", log);
+				}
+				match execution.result {
+					Ok(new_state) => {
+						state = new_state;
+						println!("New SharedState value: {}", state.show());
+					},
+					Err(err) => println!("Error: {}", err),
+				}
+				if let Some(weight) = execution.weight {
+					println!("Weight: {}", weight);
+				}
+			},
+			"state" => match SharedState::parse(argument) {
+				Ok(new_state) => state = new_state,
+				Err(err) => println!("Error: {}", err),
+			},
+			"list" => match wasm_codes.list() {
+				Ok(names) => println!("{}", names.join("\n")),
+				Err(err) => println!("Error: {}", err),
+			},
+			"quit" => break,
+			"" => (),
+			_ => println!("Unknown command, use run <wasm_code>, state <bytes>, list or quit"),
+		}
+	}
+	Ok(())
+}
@@ -1,163 +1,158 @@
+use executor_lib::{
+	charge, read_vec, write_vec, HostCost, HostFunction, HostModule, State, StoreData,
+};
+use serde::Serialize;
 use wasmtime::*;
 
 // Shared State between the Executor and all the Wams Blobs
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
+#[serde(transparent)]
 pub struct SharedState {
-    pub val: Vec<u8>,
+	pub val: Vec<u8>,
 }
 
-fn load_wasm_code(name: &str) -> Result<Vec<u8>, &'static str> {
-    use std::io::Read;
-    let path = match name.ends_with(".wasm") {
-        true => format!("wasm_codes/{}", name),
-        false => format!("wasm_codes/{}.wasm", name),
-    };
-
-    let mut f = std::fs::File::open(&path).map_err(|_| "file not found")?;
+impl State for SharedState {
+	/// Parse a SharedState from bytes separated by commas, an empty string is an empty Vec
+	fn parse(value: &str) -> Result<Self, String> {
+		let val = value
+			.split(',')
+			.map(str::trim)
+			.filter(|byte| !byte.is_empty())
+			.map(|byte| byte.parse::<u8>().map_err(|_| format!("invalid byte: {}", byte)))
+			.collect::<Result<_, _>>()?;
+		Ok(SharedState { val })
+	}
+
+	fn show(&self) -> String {
+		self.val.iter().map(u8::to_string).collect::<Vec<_>>().join(",")
+	}
+}
 
-    let mut code = Vec::new();
-    f.read_to_end(&mut code).expect("impossible read wasm_code");
-    Ok(code)
+/// Cost in fuel of the Host Functions provided to the wasm blobs, charged on top of the
+/// fuel of the wasm instructions: a fixed cost for every call plus a cost for every byte
+/// copied to or from the Linear Memory, hashed or verified
+pub const HOST_COSTS: &[(HostFunction, HostCost)] = &[
+	(("env_v1", "set_vec"), HostCost::new(100, 2)),
+	(("env_v1", "get_vec"), HostCost::new(100, 2)),
+	(("env_v2", "get_vec"), HostCost::new(100, 2)),
+	(("env_v1", "log"), HostCost::new(100, 1)),
+	(("env_v1", "panic"), HostCost::new(100, 1)),
+	(("env_v1", "set_code"), HostCost::new(10_000, 10)),
+	(("env_v1", "blake2b_256"), HostCost::new(500, 5)),
+	(("env_v1", "sha2_256"), HostCost::new(500, 5)),
+	(("env_v1", "keccak_256"), HostCost::new(500, 5)),
+	(("env_v1", "twox_64"), HostCost::new(200, 1)),
+	(("env_v1", "twox_128"), HostCost::new(200, 1)),
+	(("env_v1", "twox_256"), HostCost::new(200, 1)),
+	(("env_v1", "ed25519_verify"), HostCost::new(50_000, 5)),
+	(("env_v1", "sr25519_verify"), HostCost::new(50_000, 5)),
+	(("env_v1", "now_millis"), HostCost::new(100, 0)),
+	(("env_v1", "random_bytes"), HostCost::new(200, 5)),
+];
+
+// Host Functions used by the Advanced Executor to access the SharedState
+//
+// In this case those will be more complex than the first Rust_Executor,
+// in this case we will move Vectors between the executor and the wasm code
+// This is possible thanks to the Linear Memory
+pub struct StateModule;
+
+impl HostModule<SharedState> for StateModule {
+	// The SharedState is already in the Store
+	type Data = ();
+
+	fn namespace(&self) -> &'static str {
+		"env_v1"
+	}
+
+	fn functions(&self) -> &'static [&'static str] {
+		&["set_vec", "get_vec"]
+	}
+
+	fn register(&self, linker: &mut Linker<StoreData<SharedState>>) -> Result<()> {
+		linker.func_wrap(
+			"env_v1",
+			"set_vec",
+			|mut caller: Caller<'_, StoreData<SharedState>>, ptr: u32, size: u32| {
+				set_vec(&mut caller, ptr, size)
+			},
+		)?;
+		linker.func_wrap(
+			"env_v1",
+			"get_vec",
+			|mut caller: Caller<'_, StoreData<SharedState>>, ptr: u32, size: u32| {
+				get_vec(&mut caller, ptr, size)
+			},
+		)?;
+
+		Ok(())
+	}
 }
 
-// This function implements the Advanced Executor
-pub fn executor(name: &str, shared_state: SharedState) -> Result<SharedState, String> {
-    // Firstly the wasm code is neeed
-    let wasm_code = load_wasm_code(name).map_err(|err| err.to_string())?;
-
-    // Global compilation environment for WebAssembly
-    let engine = Engine::default();
-
-    // Compile the Wasm code into a Module,
-    // the in-memory JIT code which is ready
-    // to be execute after being instantiated
-    let module = Module::new(&engine, wasm_code).map_err(|err| err.to_string())?;
-
-    // The Store will contain all the information related to
-    // WebAssembly objects such as functions, instances, memories, etc
-    let mut store = Store::<SharedState>::new(&engine, shared_state);
-
-    // Crete the Host Functions
-    //
-    // In this case those will be more complex than the first Rust_Executor,
-    // in this case we will move Vectors between the executor and the wasm code
-    // This is possible thanks to the Linear Memory
-    let mut linker = Linker::new(&engine);
-
-    // The `set_vec` host function will get a vector from the wasm code and insert it
-    // in the SharedState. As you can the the function does no accept a normal vec but only a
-    // pointer (casted to u32) and the size of the vec, how those two arguments let us
-    // coping a vec from wasm to the executor is explained in the `read_vec` function
-    linker
-        .func_wrap(
-            "env",
-            "set_vec",
-            |mut caller: Caller<'_, SharedState>, ptr: u32, size: u32| -> Result<()> {
-                // Read the vec from Wasm Linear Memory
-                let vec = read_vec(&mut caller, ptr, size)?;
-                // Update the SharedState with the new Vec
-                caller.data_mut().val = vec;
-                Ok(())
-            },
-        )
-        .map_err(|err| err.to_string())?;
-
-    // `get_vec` host function instead write the SharedState vec to the wasm code,
-    // even if the signature is almost the same of `set_vec`. How the Vector is written in the
-    // Wasm Linear Memory is explained in the `write_vec` function.
-    linker
-        .func_wrap(
-            "env",
-            "get_vec",
-            |mut caller: Caller<'_, SharedState>, ptr: u32, size: u32| -> Result<u32> {
-                // Write the SharedState Vec to the Wasm Linear Memory
-                let size_written_vec = write_vec(&mut caller, ptr, size)?;
-                Ok(size_written_vec)
-            },
-        )
-        .map_err(|err| err.to_string())?;
-
-    // Instantiate the wasm code
-    let instance = linker
-        .instantiate(&mut store, &module)
-        .map_err(|err| err.to_string())?;
-
-    // Extract the entry point "start" end execute it
-    let start = instance
-        .get_typed_func::<(), ()>(&mut store, "start")
-        .map_err(|err| err.to_string())?;
-
-    start.call(&mut store, ()).map_err(|err| err.to_string())?;
-
-    // Just return the new SharedState
-    Ok(SharedState {
-        val: store.data().val.clone(),
-    })
+// The bodies of the Host Functions are shared with the async ones of the async executor
+
+/// The `set_vec` host function will get a vector from the wasm code and insert it
+/// in the SharedState. As you can the the function does no accept a normal vec but only a
+/// pointer (casted to u32) and the size of the vec, how those two arguments let us
+/// coping a vec from wasm to the executor is explained in the `read_vec` function
+pub fn set_vec(caller: &mut Caller<'_, StoreData<SharedState>>, ptr: u32, size: u32) -> Result<()> {
+	charge(caller, ("env_v1", "set_vec"), size as usize)?;
+	// Read the vec from Wasm Linear Memory
+	let vec = read_vec(caller, ptr, size)?;
+	// Update the SharedState with the new Vec
+	caller.data_mut().shared_state.val = vec;
+	Ok(())
 }
 
-/// The function has three input arguments:
-/// + the caller, that give us access to lot of wasm's things,
-/// such as the linear memory and other exports
-/// + the pointer to the beginning of the Vec in the wasm's Linear Memory
-/// + the size of the Vec
-pub fn read_vec(
-    caller: &mut Caller<'_, SharedState>,
-    ptr: u32,
-    size: u32,
-) -> Result<Vec<u8>, Trap> {
-    // First we need to extract the memory, which is defined
-    // as and Export in Wasm
-    let mem = match caller.get_export("memory") {
-        Some(wasmtime::Extern::Memory(mem)) => mem,
-        _ => return Err(Trap::UnreachableCodeReached),
-    };
-
-    // From the memory object we can extract the Wasm Linear Memory
-    // as a Slice and then
-    // Use the `ptr` and `size` values to get a sub-slice of the wasm-memory
-    let wasm_slice: Option<&[u8]> = mem
-        .data(&caller)
-        .get(ptr as u32 as usize..)
-        .and_then(|arr| arr.get(..size as u32 as usize));
-
-    // If the extraction of the slice from  wasm successful
-    // then translate it to a vec
-    match wasm_slice {
-        Some(w) => Ok(w.to_vec()),
-        None => Err(Trap::UnreachableCodeReached),
-    }
+/// `get_vec` host function instead write the SharedState vec to the wasm code,
+/// even if the signature is almost the same of `set_vec`. How the Vector is written in the
+/// Wasm Linear Memory is explained in the `write_vec` function.
+pub fn get_vec(
+	caller: &mut Caller<'_, StoreData<SharedState>>,
+	ptr: u32,
+	size: u32,
+) -> Result<u32> {
+	// Clone the vector from the ShareState
+	let vec = caller.data().shared_state.val.clone();
+	charge(caller, ("env_v1", "get_vec"), vec.len())?;
+	// Write the SharedState Vec to the Wasm Linear Memory
+	let size_written_vec = write_vec(caller, ptr, size, &vec)?;
+	Ok(size_written_vec)
 }
 
-// The input arguments are the same as `read_vec` but they are logically different,
-// the `ptr` and `size` refers to an area in the Wasm Linear Memory but not for read it,
-// instead it provided to let write a vector in it, the size.
+// Version 2 of the ABI changes only `get_vec`, `set_vec` is the same of the first version
 //
-// The return value is the size of the just written vec
-pub fn write_vec(
-    caller: &mut Caller<'_, SharedState>,
-    ptr: u32,
-    max_size: u32,
-) -> Result<u32, Trap> {
-    // let's get access to wasm Linear Memory
-    let mem = match caller.get_export("memory") {
-        Some(wasmtime::Extern::Memory(mem)) => mem,
-        _ => return Err(Trap::UnreachableCodeReached),
-    };
-
-    // Clone the vector from the ShareState
-    let vec = caller.data().val.clone();
-
-    // Make sure that the max size of the Vec provided by the
-    // wasm code is enough to contain the new Vec
-    if (max_size as usize) < vec.len() {
-        return Err(Trap::UnreachableCodeReached);
-    }
-
-    // Get the slice we want to use to store the new Vec from the LinearMemory
-    let wasm_buffer = &mut mem.data_mut(caller)[ptr as usize..ptr as usize + vec.len()];
-
-    // Save the Vec in the just extracted slice
-    wasm_buffer.copy_from_slice(&vec[..]);
-
-    Ok(vec.len() as u32)
+// In the first version `get_vec` traps if the SharedState does not fit in the provided area,
+// so the wasm code can only guess how big the area has to be. Now the size of the SharedState
+// is always returned and the vec is written only if it fits, so the wasm code can call it
+// again with a bigger area.
+pub struct StateModuleV2;
+
+impl HostModule<SharedState> for StateModuleV2 {
+	type Data = ();
+
+	fn namespace(&self) -> &'static str {
+		"env_v2"
+	}
+
+	fn functions(&self) -> &'static [&'static str] {
+		&["get_vec"]
+	}
+
+	fn register(&self, linker: &mut Linker<StoreData<SharedState>>) -> Result<()> {
+		linker.func_wrap(
+			"env_v2",
+			"get_vec",
+			|mut caller: Caller<'_, StoreData<SharedState>>, ptr: u32, size: u32| -> Result<u32> {
+				let vec = caller.data().shared_state.val.clone();
+				charge(&mut caller, ("env_v2", "get_vec"), vec.len())?;
+				if vec.len() <= size as usize {
+					write_vec(&mut caller, ptr, size, &vec)?;
+				}
+				Ok(vec.len() as u32)
+			},
+		)?;
+
+		Ok(())
+	}
 }
@@ -0,0 +1,6 @@
+//! The SharedState and the host modules of the Advanced Executor, used by its binary
+//! and by the examples, e.g. the async executor.
+
+mod executor;
+
+pub use executor::{get_vec, set_vec, SharedState, StateModule, StateModuleV2, HOST_COSTS};
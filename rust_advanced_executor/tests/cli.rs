@@ -0,0 +1,236 @@
+//! The subcommands and the scripts of the executor, run as a process in a directory
+//! containing its own wasm codes
+
+use std::{
+	fs,
+	path::{Path, PathBuf},
+	process::{Command, Output},
+};
+
+// Append the byte 9 to the SharedState
+const PUSH_9_WAT: &str = r#"(module
+	(import "env_v1" "get_vec" (func $get_vec (param i32 i32) (result i32)))
+	(import "env_v1" "set_vec" (func $set_vec (param i32 i32)))
+	(memory (export "memory") 1)
+	(func (export "start") (local $len i32)
+		(local.set $len (call $get_vec (i32.const 0) (i32.const 1024)))
+		(i32.store8 (local.get $len) (i32.const 9))
+		(call $set_vec (i32.const 0) (i32.add (local.get $len) (i32.const 1)))))"#;
+
+const TRAP_WAT: &str = r#"(module
+	(func (export "start") unreachable))"#;
+
+// A directory with the wasm codes `push_9` and `trap`, removed when dropped
+struct Dir(PathBuf);
+
+impl Dir {
+	fn new(test: &str) -> Self {
+		let dir = std::env::temp_dir().join(format!(
+			"rust_advanced_executor_{}_{}",
+			test,
+			std::process::id()
+		));
+		fs::create_dir_all(dir.join("wasm_codes")).unwrap();
+		for (name, wat) in [("push_9", PUSH_9_WAT), ("trap", TRAP_WAT)] {
+			let wasm_code = wat::parse_str(wat).unwrap();
+			fs::write(dir.join("wasm_codes").join(format!("{}.wasm", name)), wasm_code).unwrap();
+		}
+		Dir(dir)
+	}
+
+	fn path(&self) -> &Path {
+		&self.0
+	}
+
+	fn executor(&self, args: &[&str]) -> Output {
+		Command::new(env!("CARGO_BIN_EXE_rust_advanced_executor"))
+			.args(args)
+			.current_dir(&self.0)
+			.output()
+			.unwrap()
+	}
+}
+
+impl Drop for Dir {
+	fn drop(&mut self) {
+		let _ = fs::remove_dir_all(&self.0);
+	}
+}
+
+fn stdout(output: &Output) -> String {
+	String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+	String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn subcommands() {
+	let dir = Dir::new("subcommands");
+
+	// Every process starts from the initial SharedState, or the one of `--state`
+	let run = dir.executor(&["run", "push_9"]);
+	assert_eq!(run.status.code(), Some(0));
+	assert_eq!(stdout(&run), "1,2,3,9\n");
+	assert_eq!(stdout(&dir.executor(&["--state", "5", "run", "push_9"])), "5,9\n");
+
+	// The previous wasm code is kept between the processes
+	let previous = dir.executor(&["--state", "7", "run-previous"]);
+	assert_eq!(previous.status.code(), Some(0));
+	assert_eq!(stdout(&previous), "7,9\n");
+
+	let trap = dir.executor(&["run", "trap"]);
+	assert_eq!(trap.status.code(), Some(1));
+	assert!(stderr(&trap).starts_with("Error: "));
+	assert_eq!(dir.executor(&["run", "missing"]).status.code(), Some(1));
+	assert_eq!(dir.executor(&["set-state", "256"]).status.code(), Some(2));
+	assert_eq!(dir.executor(&["unknown"]).status.code(), Some(2));
+
+	assert_eq!(stdout(&dir.executor(&["list"])), "push_9.wasm\ntrap.wasm\n");
+
+	let json = dir.executor(&["--json", "run", "push_9"]);
+	let report: serde_json::Value = serde_json::from_slice(&json.stdout).unwrap();
+	assert_eq!(report["status"], "success");
+	assert_eq!(report["output"], serde_json::json!([1, 2, 3, 9]));
+}
+
+#[test]
+fn run_previous_after_upgrade() {
+	let dir = Dir::new("after_upgrade");
+	// Propose the code of `push_9` with `set_code`, the SharedState is not changed
+	let push_9 = wat::parse_str(PUSH_9_WAT).unwrap();
+	let data: String = push_9.iter().map(|byte| format!("\\{:02x}", byte)).collect();
+	let upgrade = format!(
+		r#"(module
+			(import "env_v1" "set_code" (func $set_code (param i32 i32)))
+			(memory (export "memory") 1)
+			(data (i32.const 0) "{}")
+			(func (export "start") (call $set_code (i32.const 0) (i32.const {}))))"#,
+		data,
+		push_9.len()
+	);
+	fs::write(dir.path().join("wasm_codes/upgrade.wasm"), wat::parse_str(upgrade).unwrap())
+		.unwrap();
+
+	let run = stdout(&dir.executor(&["run", "upgrade"]));
+	assert!(run.starts_with("1,2,3\nupgrade\tupgrade@"));
+
+	// The upgraded code is executed by a new process
+	let previous = dir.executor(&["run-previous"]);
+	assert_eq!(previous.status.code(), Some(0));
+	assert_eq!(stdout(&previous), "1,2,3,9\n");
+}
+
+#[test]
+fn run_previous_without_previous() {
+	let dir = Dir::new("without_previous");
+
+	let previous = dir.executor(&["run-previous"]);
+	assert_eq!(previous.status.code(), Some(1));
+	assert_eq!(stderr(&previous), "Error: No previous wasm blob\n");
+}
+
+#[test]
+fn pipeline() {
+	let dir = Dir::new("pipeline");
+
+	let stopped = dir.executor(&["pipeline", "push_9, trap, push_9"]);
+	assert_eq!(stopped.status.code(), Some(1));
+	// One record per line, the error and the backtrace of the failed stage are two fields
+	let stopped = stdout(&stopped);
+	let records: Vec<Vec<&str>> = stopped.lines().map(|line| line.split('\t').collect()).collect();
+	assert_eq!(records.len(), 3);
+	assert_eq!(records[0], ["push_9", "ok", "1,2,3,9"]);
+	assert_eq!(records[1][..2], ["trap", "failed"]);
+	assert_eq!(records[1].len(), 4);
+	assert_eq!(records[2], ["push_9", "skipped"]);
+
+	let kept_going = dir.executor(&["pipeline", "push_9,trap,push_9", "--keep-going"]);
+	assert_eq!(kept_going.status.code(), Some(1));
+	assert!(stdout(&kept_going).ends_with("push_9\tok\t1,2,3,9,9\n"));
+}
+
+#[test]
+fn script() {
+	let dir = Dir::new("script");
+
+	fs::write(
+		dir.path().join("ok.script"),
+		"# the state is kept between the lines\nset-state 4\nrun push_9\n\nrun-previous\n",
+	)
+	.unwrap();
+	let ok = dir.executor(&["script", "ok.script"]);
+	assert_eq!(ok.status.code(), Some(0));
+	assert_eq!(stdout(&ok), "4\n4,9\n4,9,9\n");
+
+	// The script stops at the first line that fails
+	fs::write(dir.path().join("failed.script"), "run push_9\nrun trap\nrun push_9\n").unwrap();
+	let failed = dir.executor(&["script", "failed.script"]);
+	assert_eq!(failed.status.code(), Some(1));
+	assert_eq!(stdout(&failed), "1,2,3,9\n");
+	assert!(stderr(&failed).starts_with("Error: line 2: "));
+
+	fs::write(dir.path().join("invalid.script"), "run push_9\nexecute push_9\n").unwrap();
+	let invalid = dir.executor(&["script", "invalid.script"]);
+	assert_eq!(invalid.status.code(), Some(2));
+	assert_eq!(stdout(&invalid), "1,2,3,9\n");
+	assert!(stderr(&invalid).starts_with("Error: line 2: "));
+}
+
+#[test]
+fn throughput() {
+	let dir = Dir::new("throughput");
+
+	let throughput = dir.executor(&["throughput", "push_9", "--executions", "10"]);
+	assert_eq!(throughput.status.code(), Some(0));
+	let throughput = stdout(&throughput);
+	let fields: Vec<&str> =
+		throughput.lines().map(|line| line.split('\t').next().unwrap()).collect();
+	assert_eq!(fields, ["executions", "seconds", "per_second"]);
+	assert!(throughput.starts_with("executions\t10\n"));
+
+	assert_eq!(dir.executor(&["throughput", "trap"]).status.code(), Some(1));
+}
+
+// The guest crates are built only if the wasm target is installed, e.g. not without rustup
+fn wasm_target_installed() -> bool {
+	Command::new("rustup")
+		.args(["target", "list", "--installed"])
+		.output()
+		.map(|output| stdout(&output).lines().any(|target| target == "wasm32-unknown-unknown"))
+		.unwrap_or(false)
+}
+
+#[test]
+fn build() {
+	if !wasm_target_installed() {
+		eprintln!("skipped: the wasm32-unknown-unknown target is not installed");
+		return
+	}
+	let dir = Dir::new("build");
+	let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../wasm_code");
+	let crate_dir = crate_dir.to_str().unwrap();
+
+	let built =
+		dir.executor(&["build", crate_dir, "--exercise", "ex4", "--alias", "ex", "--tag", "v1"]);
+	assert_eq!(built.status.code(), Some(0));
+	let built = stdout(&built);
+	let hash = built.strip_prefix("ex\t").unwrap().trim_end();
+	assert_eq!(hash.len(), 64);
+
+	// The built wasm code is identified by the alias and by the tag
+	for name in ["ex", "ex:v1"] {
+		let info = stdout(&dir.executor(&["info", name]));
+		assert!(info.starts_with(&format!("hash\t{}\n", hash)), "{}", name);
+	}
+
+	// The tag already identifies another wasm code
+	let collision =
+		dir.executor(&["build", crate_dir, "--exercise", "ex1", "--alias", "ex", "--tag", "v1"]);
+	assert_eq!(collision.status.code(), Some(1));
+	// The output of cargo comes first on stderr
+	let error = format!("Error: ex:v1 already identifies the wasm code {}\n", &hash[..12]);
+	assert!(stderr(&collision).ends_with(&error));
+	assert!(stdout(&dir.executor(&["info", "ex:v1"])).starts_with(&format!("hash\t{}\n", hash)));
+}
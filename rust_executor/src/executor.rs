@@ -1,66 +1,94 @@
-#![allow(unused_variables)]
+#![allow(unused_variables, unreachable_code)]
+use executor_lib::{ExecutionSteps, HostModule, State, StoreData};
+use serde::Serialize;
 use wasmtime::*;
 
 /// Shared State between the Executor and all the Wasm Blobs
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
+#[serde(transparent)]
 pub struct SharedState {
 	pub val: u32,
 }
 
-/// Helper unction to load a wasm file
-/// If the name provided does not end with `.wasm` it is appended.
-fn load_wasm_code(name: &str) -> Result<Vec<u8>, &'static str> {
-	use std::io::Read;
+impl State for SharedState {
+	fn parse(value: &str) -> Result<Self, String> {
+		let val = value.trim().parse().map_err(|_| format!("invalid state: {}", value))?;
+		Ok(SharedState { val })
+	}
 
-	// If the file does not end with a `.wasm` extension, add it.
-	let path = match name.ends_with(".wasm") {
-		true => format!("wasm_codes/{}", name),
-		false => format!("wasm_codes/{}.wasm", name),
-	};
-
-	let mut f = std::fs::File::open(&path).map_err(|_| "file not found")?;
-
-	let mut code = Vec::new();
-	f.read_to_end(&mut code).expect("impossible read wasm_code");
-	Ok(code)
+	fn show(&self) -> String {
+		self.val.to_string()
+	}
 }
 
-/// This function implements the Executor,
+/// The steps of every execution of a wasm blob,
 /// you will use wasmtime as Embedder of the wasm code and all the needed documentation
 /// is here: https://docs.rs/wasmtime/latest/wasmtime/
 ///
-/// It accepts the name of the wasm blob that needs to be executed, and
-/// the current SharedState
-///
-/// The Result is the new SharedState or an Error containing a String describing the Error
-pub fn executor(name: &str, shared_state: SharedState) -> Result<SharedState, String> {
-	let wasm_code = load_wasm_code(name).map_err(|err| err.to_string())?;
+/// `executor_lib` loads the wasm code, compiles it with the Engine into a Module and
+/// resolves its imports once with a Linker containing the Host Functions of `StateModule`
+/// and of the other host modules. Then every execution creates a Store and instantiates the
+/// wasm code to execute its entry point `start`, the new SharedState is read from the Store.
+pub struct Steps;
 
-	let engine = Engine::default();
+impl ExecutionSteps<SharedState> for Steps {
+	/// It accepts the Engine, the global compilation environment for WebAssembly,
+	/// and the data of the Store, containing the current SharedState
+	fn new_store(
+		&self,
+		engine: &Engine,
+		data: StoreData<SharedState>,
+	) -> Store<StoreData<SharedState>> {
+		// TODO 1:
+		// Create the Store, which will contain all the information related to
+		// WebAssembly objects such as functions, instances, memories, etc
+		//
+		// The Store allows inserting also arbitrary data (StoreData)
+		todo!()
+	}
+
+	/// It accepts the wasm code already linked to the Host Functions,
+	/// see `InstancePre`, and the Store created by `new_store`
+	fn call_start(
+		&self,
+		instance_pre: &InstancePre<StoreData<SharedState>>,
+		store: &mut Store<StoreData<SharedState>>,
+	) -> Result<()> {
+		// TODO 3:
+		// Instantiate the wasm code
+		todo!();
+
+		// TODO 4:
+		// Extract the entry point "start" from the just instantiated code
+		// end execute it!!
+		todo!()
+	}
+}
 
-	let module = Module::new(&engine, wasm_code).map_err(|err| err.to_string())?;
+/// The Host Functions used by the wasm blobs to access the SharedState
+pub struct StateModule;
 
-	// TODO 1:
-	// Create the Store, which will contain all the information related to
-	// WebAssembly objects such as functions, instances, memories, etc
-	//
-	// The Store allows inserting also arbitrary data (SharedState)
-	todo!();
+impl HostModule<SharedState> for StateModule {
+	// The SharedState is already in the Store
+	type Data = ();
 
-	// TODO 2:
-	// Crete the Host Functions
-	todo!();
+	fn namespace(&self) -> &'static str {
+		"env_v1"
+	}
 
-	// TODO 3:
-	// Instantiate the wasm code
-	todo!();
+	fn functions(&self) -> &'static [&'static str] {
+		&["get", "set"]
+	}
 
-	// TODO 4:
-	// Extract the entry point "start" from the just instantiated link
-	// end execute it!!
-	todo!();
+	/// It accepts the Linker that on instantiation time will link the Host Functions
+	/// to the wasm code. The Store of every execution contains a `StoreData`,
+	/// the SharedState is in its `shared_state` field.
+	fn register(&self, linker: &mut Linker<StoreData<SharedState>>) -> Result<()> {
+		// TODO 2:
+		// Create the Host Functions: `get` returns the value of the SharedState
+		// and `set` changes it
+		todo!();
 
-	// TODO 5:
-	// Just return the new SharedState
-	todo!()
+		Ok(())
+	}
 }
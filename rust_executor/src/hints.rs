@@ -2,27 +2,31 @@
  * TODO 1:
  * The object Store will be usable in each host function,
  * the SharedState that you need to be able to modify from the
- * HostFunctions needs to be inserted here
+ * HostFunctions is in the StoreData that needs to be inserted here
  *
  * TODO 2:
- * You will need to create before something called Linker
- * that on instantiation time will link the provided host
- * functions to the wasm code
+ * The Linker received by `register` will link the provided host
+ * functions to the wasm code on instantiation time.
+ * It provides `func_wrap` to define a Host Function from a closure
  *
  * The default module name used in the wasm code for the
- * host functions is "env"
+ * host functions is "env", but the host functions are defined
+ * in the namespace of their ABI version, the one returned by
+ * `namespace`. The executor_lib makes them available also in "env"
  *
  * In the closure used to implement the HostFunctions you will be able
  * to access the Storage, how? using the Caller object
  * (mandatory first parameter of the closure) you can use the `.data()`
- * or `.data_mut()` to retrieve the Store object
+ * or `.data_mut()` to retrieve the Store object.
+ * `set` is the same as `get` but the closure accepts the new value
+ * as second parameter, look into `.data_mut()`
  *
  * TODO 3:
- * You can use the the just created Linker,
- * which will automatically resolve the imports
+ * The imports have already been resolved by the Linker,
+ * look into `InstancePre::instantiate`
  *
  * TODO 4:
  * You can use the instantiated code to extract the entry point,
- * if you can't figure out how then look into `get_type_func`
+ * if you can't figure out how then look into `get_typed_func`
  *
  * */
@@ -1,32 +1,27 @@
 #![allow(unused_variables)]
+// Used by the TODO 1
+#[allow(unused_imports)]
+use executor_lib::load_wasm_code;
 use wasmtime::*;
 
-/// Helper unction to load a wasm file
-/// If the name provided does not end with `.wasm` it is appended.
-fn load_wasm_code(name: &str) -> Result<Vec<u8>, &'static str> {
-	use std::io::Read;
-
-	// If the file does not end with a `.wasm` extension, add it.
-	let path = match name.ends_with(".wasm") {
-		true => format!("{}", name),
-		false => format!("{}.wasm", name),
-	};
-
-	let mut f = std::fs::File::open(&path).map_err(|_| "file not found")?;
-
-	let mut code = Vec::new();
-	f.read_to_end(&mut code).expect("impossible read wasm_code");
-	Ok(code)
-}
+/// The wasm code of the exercise 1 built in `wasm_code`, the path is relative
+/// to this crate and the `.wasm` extension is added by `load_wasm_code`
+/// of `executor_lib`
+pub const WASM_CODE: &str = "../wasm_code/target/wasm32-unknown-unknown/release/ex1";
 
 // This function implements the Executor,
 // you will use wasmtime as Embedder of the wasm code and all the needed documentation
 // is here: https://docs.rs/wasmtime/latest/wasmtime/
-pub fn executor() -> Result<(), String> {
+//
+// It returns the result of the division `x / y` computed by the wasm code
+// The placeholders `todo!()` make the code after them unreachable
+#[allow(unreachable_code, clippy::let_unit_value)]
+pub fn executor(x: i32, y: i32) -> Result<i32, String> {
 	// TODO 1:
 	// Firstly, the wasm code is needed,
 	// It is represented in a binary format so we will just load it
-	// from the file (you can the use the function just right above).
+	// from the file `WASM_CODE` (you can use the function `load_wasm_code`
+	// imported above).
 	let wasm_code = todo!();
 
 	// TODO 2:
@@ -58,12 +53,9 @@ pub fn executor() -> Result<(), String> {
 
 	// TODO 7:
 	// Execute the wasm function!
-	let (x, y) = (10, 2);
 	let result: i32 = todo!();
 
-	println!("{x} / {y} = {result}");
-
-	Ok(())
+	Ok(result)
 }
 
 // After you have made this executor work, you should explore the python executor
@@ -6,8 +6,38 @@
 
 mod executor;
 
-use executor::executor;
+use clap::Parser;
+use executor::{executor, WASM_CODE};
+use executor_lib::{blob_hash, load_wasm_code, Report, EXIT_FAILURE};
+use std::time::Instant;
+
+#[derive(Parser)]
+struct Cli {
+	/// Print a JSON report of the execution instead of the result
+	#[arg(long)]
+	json: bool,
+}
 
 fn main() {
-	executor().unwrap();
+	let cli = Cli::parse();
+
+	let (x, y) = (10, 2);
+
+	let now = Instant::now();
+	let result = executor(x, y);
+	let elapsed = now.elapsed();
+
+	if cli.json {
+		// This executor has no SharedState, the input are the arguments of `div`
+		// and the output is its result
+		let hash = load_wasm_code(WASM_CODE).ok().as_deref().map(blob_hash);
+		let failed = result.is_err();
+		Report::new(WASM_CODE, hash, (x, y), result, elapsed).print();
+		if failed {
+			std::process::exit(EXIT_FAILURE);
+		}
+	} else {
+		let result = result.unwrap();
+		println!("{x} / {y} = {result}");
+	}
 }
@@ -1,5 +1,5 @@
-#![allow(unused_variables)]
-/// ATTENTION: Before you can use this code, you must change the path in `Cargo.toml`
+#![allow(unused_variables, dead_code)]
+/// ATTENTION: This code is the crate `ex4` of `wasm_code`, built into `ex4.wasm`
 
 // Here we define the signatures of the Host Functions described in the Readme
 // extern "C" lets us define the signatures of the host functions
@@ -8,8 +8,9 @@
 // Those function will be translated to "Imports" in wasm,
 // and provided by the executor
 extern "C" {
-    todo!("define set function");
-    todo!("define get function");
+    // TODO define the set function, e.g.
+    // fn set(val: u32);
+    // TODO define the get function
 }
 
 // One inconvenience with the host function definition above is that
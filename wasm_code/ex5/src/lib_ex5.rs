@@ -0,0 +1,27 @@
+// The host functions let us work with a Vector SharedState,
+// set_vec and get_vec are not directly used in the code
+// because they require to implement a FFI (Function Foreign Interface).
+// What's FFI? It is just a way to make possible the sharing of more complex
+// data then just integers with the Embedder.
+//
+// `executor_sdk` declares the host functions of the executors and wraps them
+// in safe functions, in `executor_sdk/src/vec_state.rs` you can see how
+// `set_vec_hf` and `get_vec_hf` pass the vectors through the LinearMemory.
+use executor_sdk::vec_state::{get_vec_hf, set_vec_hf};
+
+// Embed the metadata read by the executor, this code uses the first ABI version
+executor_sdk::metadata!(name: "mul_vec_3", abi: 1);
+
+// Define the entry point `start`, it calls `mul_vec_3`
+executor_sdk::start!(mul_vec_3);
+
+fn mul_vec_3() {
+    // Implementation Test: Multiply each element by 3
+    let mut vec = get_vec_hf();
+
+    for v in vec.iter_mut() {
+        *v *= 3;
+    }
+
+    set_vec_hf(&vec);
+}